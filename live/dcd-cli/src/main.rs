@@ -0,0 +1,337 @@
+//! Command-line tooling for clickpacks and macros, without the game or a window.
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use dcd_core::{
+    clickpack::{Clickpack, LoadClickpackFor},
+    engine::ClickSettings,
+    render::{self, RenderOptions},
+    replay,
+    tiers::Tiers,
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path, path::PathBuf};
+
+const CLICKPACK_DB_URL: &str =
+    "https://raw.githubusercontent.com/zeozeozeo/clickpack-db/main/db.json";
+
+#[derive(Parser)]
+#[command(version, about = "Clickpack and macro tooling for DcD Live")]
+struct Cli {
+    /// Print what's going on while loading and rendering
+    #[arg(short, long, global = true)]
+    verbose: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show how many sounds were found in each clickpack folder and any problems with them
+    Inspect {
+        clickpack: PathBuf,
+        /// Sort sounds into the tiers from this config.json
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+    },
+    /// Render a macro (.gdr, .gdr.json, .txt or .json event list) to a WAV file
+    Render {
+        clickpack: PathBuf,
+        replay: PathBuf,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Read click and sound settings from this config.json
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        #[arg(long, default_value_t = 48000)]
+        sample_rate: u32,
+        /// Seconds of audio to keep after the last sound
+        #[arg(long, default_value_t = 0.5)]
+        tail: f64,
+        /// Mix in the clickpack's noise file
+        #[arg(long)]
+        noise: bool,
+        /// Seed for random choices, so renders can be reproduced (overrides the config)
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Ticks per second of the macro, for timings in frames (overrides the config)
+        #[arg(long)]
+        tps: Option<f64>,
+    },
+    /// Check a config.json for values the bot can't use
+    CheckConfig { config: PathBuf },
+    /// List clickpacks available on ClickpackDB
+    Db {
+        /// Only show clickpacks whose name contains this
+        filter: Option<String>,
+        #[arg(long, default_value = CLICKPACK_DB_URL)]
+        url: String,
+    },
+}
+
+fn load_clickpack(path: &Path, tiers: Tiers) -> Result<Clickpack> {
+    let mut clickpack = Clickpack {
+        tiers,
+        ..Default::default()
+    };
+    clickpack.load_from_path(path, LoadClickpackFor::All)?;
+    Ok(clickpack)
+}
+
+fn read_config(path: &Path) -> Result<serde_json::Value> {
+    let data = std::fs::read_to_string(path).with_context(|| format!("failed to read {path:?}"))?;
+    serde_json::from_str(&data).with_context(|| format!("{path:?} is not valid json"))
+}
+
+fn print_report(clickpack: &Clickpack) {
+    let diagnostics = clickpack.report.diagnostics();
+    if !diagnostics.is_empty() {
+        println!();
+    }
+    for diagnostic in diagnostics {
+        println!("{diagnostic}");
+    }
+}
+
+fn inspect(path: &Path, config: Option<&Path>) -> Result<()> {
+    let mut clickpack = Clickpack::default();
+    if let Some(config) = config {
+        clickpack.tiers = ClickSettings::deserialize(&read_config(config)?)?.tiers();
+    }
+    if let Err(e) = clickpack.load_from_path(path, LoadClickpackFor::All) {
+        // the report usually explains why nothing was loaded
+        print_report(&clickpack);
+        return Err(e);
+    }
+    println!("{}: {} sounds", clickpack.name, clickpack.num_sounds);
+    if let Some(manifest) = &clickpack.manifest {
+        if let Some(author) = &manifest.author {
+            println!("author: {author}");
+        }
+        if let Some(version) = &manifest.version {
+            println!("version: {version}");
+        }
+        if let Some(description) = &manifest.description {
+            println!("description: {description}");
+        }
+    }
+    println!(
+        "noise: {}",
+        if clickpack.has_noise() { "yes" } else { "no" }
+    );
+    println!(
+        "platformer sounds: {}",
+        if clickpack.has_platformer_sounds {
+            "yes"
+        } else {
+            "no"
+        }
+    );
+    for (name, clicks) in clickpack.players() {
+        if clicks.num_sounds() == 0 {
+            continue;
+        }
+        println!("{name}: {} sounds", clicks.num_sounds());
+        for (folder, sounds) in clicks.folders(&clickpack.tiers) {
+            println!("    {folder}: {}", sounds.len());
+        }
+        for layer in clicks.layers.iter().flatten() {
+            let path = layer.path.strip_prefix(path).unwrap_or(&layer.path);
+            println!(
+                "    layer {path:?}: {} (volume {}, jitter {} ms)",
+                layer.sounds.len(),
+                layer.settings.volume,
+                layer.settings.jitter * 1000.0
+            );
+        }
+    }
+    print_report(&clickpack);
+    Ok(())
+}
+
+fn render(
+    clickpack: &Path,
+    replay: &Path,
+    output: &Path,
+    config: Option<&Path>,
+    seed: Option<u64>,
+    tps: Option<f64>,
+    mut options: RenderOptions,
+) -> Result<()> {
+    let mut settings = ClickSettings::default();
+    if let Some(config) = config {
+        let value = read_config(config)?;
+        settings = ClickSettings::deserialize(&value)?;
+        let noise = options.play_noise;
+        options = RenderOptions {
+            sample_rate: options.sample_rate,
+            tail: options.tail,
+            ..RenderOptions::deserialize(&value)?
+        };
+        options.play_noise |= noise;
+    }
+    if seed.is_some() {
+        settings.seed = seed;
+    }
+    if let Some(tps) = tps {
+        settings.tps = tps;
+    }
+    for problem in settings
+        .validate()
+        .into_iter()
+        .chain(options.dsp.validate())
+    {
+        log::warn!("config: {problem}");
+    }
+    let events = replay::load_replay(replay)?;
+    let mut clickpack = load_clickpack(clickpack, settings.tiers())?;
+    render::render_to_wav(output, &mut clickpack, &settings, &options, &events)?;
+    println!("rendered {} events to {output:?}", events.len());
+    Ok(())
+}
+
+fn check_config(path: &Path) -> Result<()> {
+    let value = read_config(path)?;
+    let settings = ClickSettings::deserialize(&value).context("invalid click settings")?;
+    let options = RenderOptions::deserialize(&value).context("invalid sound settings")?;
+    let mut problems = settings.validate();
+    problems.extend(options.dsp.validate());
+    if problems.is_empty() {
+        println!("{path:?}: ok");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{path:?}: {problem}");
+    }
+    anyhow::bail!("found {} problem(s) in {path:?}", problems.len())
+}
+
+#[derive(Deserialize)]
+struct ClickpackDbEntry {
+    size: u64,
+    #[serde(default)]
+    has_noise: bool,
+    url: String,
+}
+
+/// The index at [`CLICKPACK_DB_URL`]. Other fields of the index are ignored.
+#[derive(Deserialize)]
+struct ClickpackDb {
+    clickpacks: BTreeMap<String, ClickpackDbEntry>,
+}
+
+impl ClickpackDb {
+    fn parse(reader: impl std::io::Read) -> Result<Self> {
+        serde_json::from_reader(reader).context("invalid ClickpackDB index")
+    }
+
+    /// One line per clickpack whose name contains `filter`, ignoring case.
+    fn lines(&self, filter: Option<&str>) -> Vec<String> {
+        let filter = filter.map(str::to_lowercase);
+        self.clickpacks
+            .iter()
+            .filter(|(name, _)| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| name.to_lowercase().contains(f))
+            })
+            .map(|(name, entry)| {
+                format!(
+                    "{name}\t{:.2} MB\t{}\t{}",
+                    entry.size as f64 / 1_000_000.0,
+                    if entry.has_noise { "noise" } else { "-" },
+                    entry.url
+                )
+            })
+            .collect()
+    }
+}
+
+fn list_db(url: &str, filter: Option<&str>) -> Result<()> {
+    log::info!("fetching {url}");
+    let response = ureq::get(url)
+        .call()
+        .with_context(|| format!("failed to fetch {url}"))?;
+    let db = ClickpackDb::parse(response.into_reader())?;
+    for line in db.lines(filter) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    simple_logger::SimpleLogger::new()
+        .with_level(if cli.verbose {
+            log::LevelFilter::Info
+        } else {
+            log::LevelFilter::Warn
+        })
+        .init()?;
+
+    match cli.command {
+        Command::Inspect { clickpack, config } => inspect(&clickpack, config.as_deref()),
+        Command::Render {
+            clickpack,
+            replay,
+            output,
+            config,
+            sample_rate,
+            tail,
+            noise,
+            seed,
+            tps,
+        } => render(
+            &clickpack,
+            &replay,
+            &output,
+            config.as_deref(),
+            seed,
+            tps,
+            RenderOptions {
+                sample_rate,
+                tail,
+                play_noise: noise,
+                ..Default::default()
+            },
+        ),
+        Command::CheckConfig { config } => check_config(&config),
+        Command::Db { filter, url } => list_db(&url, filter.as_deref()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DB: &[u8] = include_bytes!("../fixtures/clickpack_db.json");
+
+    #[test]
+    fn parses_clickpack_db() {
+        let db = ClickpackDb::parse(DB).unwrap();
+        assert_eq!(db.clickpacks.len(), 3);
+        let akira = &db.clickpacks["Akira Clicks"];
+        assert_eq!(akira.size, 2516843);
+        assert!(akira.has_noise);
+        assert!(akira.url.ends_with("/Akira%20Clicks.zip"));
+    }
+
+    #[test]
+    fn filters_clickpack_db() {
+        let db = ClickpackDb::parse(DB).unwrap();
+        assert_eq!(
+            db.lines(Some("CHERRY")),
+            ["cherry mx blue\t0.48 MB\t-\thttps://github.com/zeozeozeo/clickpack-db/raw/main/out/cherry%20mx%20blue.zip"]
+        );
+        assert_eq!(db.lines(None).len(), 3);
+    }
+
+    #[test]
+    fn rejects_other_shapes() {
+        // a flat map of clickpacks, or entries without a url
+        assert!(ClickpackDb::parse(&br#"{"Akira Clicks": {"size": 1, "url": ""}}"#[..]).is_err());
+        assert!(
+            ClickpackDb::parse(&br#"{"clickpacks": {"Akira Clicks": {"size": 1}}}"#[..]).is_err()
+        );
+    }
+}
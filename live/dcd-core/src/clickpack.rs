@@ -0,0 +1,1163 @@
+use crate::{
+    loudness::{self, db_to_gain, Loudness, LoudnessCache},
+    manifest::{ClickpackManifest, LayerSettings, MANIFEST_FILENAME},
+    onset,
+    report::ClickpackReport,
+    source::ClickpackSource,
+    tiers::Tiers,
+};
+use anyhow::{Context, Result};
+use kittyaudio::Sound;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Button {
+    Jump = 1,
+    Left = 2,
+    Right = 3,
+}
+
+impl Button {
+    pub fn from_u8(b: u8) -> Self {
+        Self::try_from_u8(b).unwrap_or_else(|| panic!("invalid button value {b}, expected 1..=3"))
+    }
+
+    pub const fn try_from_u8(b: u8) -> Option<Self> {
+        match b {
+            1 => Some(Self::Jump),
+            2 => Some(Self::Left),
+            3 => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub const fn is_platformer(self) -> bool {
+        matches!(self, Self::Left | Self::Right)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Timings {
+    pub hard: f64,
+    pub regular: f64,
+    pub soft: f64,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Self {
+            hard: 2.0,
+            regular: 0.15,
+            soft: 0.025,
+            // lower = microclicks
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Pitch {
+    pub from: f64,
+    pub to: f64,
+}
+
+impl Default for Pitch {
+    fn default() -> Self {
+        Self {
+            from: 0.98,
+            to: 1.02,
+        }
+    }
+}
+
+/// Stereo position of clicks, from -1 (left) to 1 (right).
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PanSettings {
+    pub player1: f64,
+    pub player2: f64,
+    /// Added to the player's pan for the platformer left button.
+    pub left: f64,
+    /// Added to the player's pan for the platformer right button.
+    pub right: f64,
+    /// Largest random offset added to every click.
+    pub jitter: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct VolumeSettings {
+    pub enabled: bool,
+    pub spam_time: f64,
+    pub spam_vol_offset_factor: f64,
+    pub max_spam_vol_offset: f64,
+    pub change_releases_volume: bool,
+    pub global_volume: f64,
+    pub volume_var: f64,
+    pub platformer_volume_factor: f64,
+    /// Bring every folder to `normalize_target` before applying the other settings.
+    #[serde(default = "bool::default")]
+    pub normalize: bool,
+    /// Target loudness in dB RMS.
+    #[serde(default = "normalize_target_default")]
+    pub normalize_target: f64,
+}
+
+const fn normalize_target_default() -> f64 {
+    -20.0
+}
+
+impl Default for VolumeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            spam_time: 0.3,
+            spam_vol_offset_factor: 1.3,
+            max_spam_vol_offset: 0.6,
+            change_releases_volume: false,
+            global_volume: 1.0,
+            volume_var: 0.2,
+            platformer_volume_factor: 1.0,
+            normalize: false,
+            normalize_target: normalize_target_default(),
+        }
+    }
+}
+
+/// Category of an action: a push or release in one of the [`Tiers`], by index.
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ClickType {
+    Click(usize),
+    Release(usize),
+    #[default]
+    None,
+}
+
+impl ClickType {
+    pub const fn new(tier: usize, release: bool) -> Self {
+        if release {
+            Self::Release(tier)
+        } else {
+            Self::Click(tier)
+        }
+    }
+
+    /// Position of this category in [`Tiers::categories`], clicks and releases of the
+    /// first tier first.
+    pub const fn index(self) -> Option<usize> {
+        match self {
+            Self::Click(tier) => Some(tier * 2),
+            Self::Release(tier) => Some(tier * 2 + 1),
+            Self::None => None,
+        }
+    }
+
+    #[inline]
+    pub const fn is_release(self) -> bool {
+        matches!(self, Self::Release(_))
+    }
+
+    #[inline]
+    pub const fn is_click(self) -> bool {
+        matches!(self, Self::Click(_))
+    }
+}
+
+#[derive(Clone)]
+pub struct SoundWrapper {
+    pub sound: Sound,
+    /// File the sound was loaded from. Inside zipped clickpacks this is the path the
+    /// file would have if the archive was a folder.
+    pub path: PathBuf,
+    /// How likely this sound is to be picked compared to the others in its folder.
+    pub weight: f64,
+    pub loudness: Option<Loudness>,
+    /// Gain that brings the average loudness of this sound's folder to 0 dB, 1 if it
+    /// wasn't measured.
+    pub norm_gain: f64,
+    /// Frame where the click's transient starts, everything before it is near-silent.
+    pub onset: usize,
+    // fmod_sound: *mut FMOD_SOUND,
+}
+
+/// Weight from a filename suffix like `click@2.wav`.
+fn weight_from_filename(path: &Path) -> Option<f64> {
+    let stem = path.file_stem()?.to_str()?;
+    let (_, weight) = stem.rsplit_once('@')?;
+    let weight: f64 = weight.parse().ok()?;
+    (weight.is_finite() && weight >= 0.0).then_some(weight)
+}
+
+impl SoundWrapper {
+    pub fn new(path: &Path, sound: Sound) -> Self {
+        Self {
+            onset: onset::detect(&sound),
+            sound,
+            path: path.to_path_buf(),
+            weight: weight_from_filename(path).unwrap_or(1.0),
+            loudness: None,
+            norm_gain: 1.0,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self> {
+        // load kittyaudio sound
+        let sound = Sound::from_path(path)?;
+        Ok(Self::new(path, sound))
+    }
+
+    /// Decode a sound from an encoded file in memory.
+    pub fn from_bytes(path: &Path, data: Vec<u8>) -> Result<Self> {
+        let sound = Sound::from_cursor(Cursor::new(data))?;
+        Ok(Self::new(path, sound))
+    }
+
+    /// Length of the silence before the onset, in seconds.
+    pub fn onset_secs(&self) -> f64 {
+        self.onset as f64 / self.sample_rate() as f64
+    }
+
+    /// Volume multiplier that brings this sound's folder to `target_db` RMS.
+    pub fn normalize_gain(&self, target_db: f64) -> f64 {
+        self.norm_gain * db_to_gain(target_db)
+    }
+}
+
+/// Pick a sound with a chance proportional to its weight. Sounds with a weight of 0 are
+/// never picked. Sounds in `recent` are skipped, except for the oldest ones if there
+/// wouldn't be anything left to pick.
+fn pick_weighted<'a>(
+    rng: &mut fastrand::Rng,
+    sounds: &'a [SoundWrapper],
+    recent: &[PathBuf],
+) -> Option<&'a SoundWrapper> {
+    pick_weighted_matching(rng, sounds, recent, |_| true)
+}
+
+/// [`pick_weighted`] out of the sounds `filter` keeps.
+fn pick_weighted_matching<'a>(
+    rng: &mut fastrand::Rng,
+    sounds: &'a [SoundWrapper],
+    recent: &[PathBuf],
+    filter: impl Fn(&SoundWrapper) -> bool,
+) -> Option<&'a SoundWrapper> {
+    let pickable = |s: &&SoundWrapper| s.weight > 0.0 && filter(s);
+    let len = sounds.iter().filter(pickable).count();
+    if len == 0 {
+        return None;
+    }
+    let recent = &recent[recent.len().saturating_sub(len - 1)..];
+    let candidates = || {
+        sounds
+            .iter()
+            .filter(pickable)
+            .filter(|s| !recent.contains(&s.path))
+    };
+
+    let total: f64 = candidates().map(|s| s.weight).sum();
+    let mut r = rng.f64() * total;
+    for sound in candidates() {
+        if r < sound.weight {
+            return Some(sound);
+        }
+        r -= sound.weight;
+    }
+    candidates().next_back() // float error
+}
+
+impl std::ops::Deref for SoundWrapper {
+    type Target = Sound;
+
+    fn deref(&self) -> &Self::Target {
+        &self.sound
+    }
+}
+
+impl std::ops::DerefMut for SoundWrapper {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.sound
+    }
+}
+
+/// Sounds played on top of every click of a category, from a folder like `clicks_layer`.
+#[derive(Clone)]
+pub struct Layer {
+    pub path: PathBuf,
+    pub sounds: Vec<SoundWrapper>,
+    pub settings: LayerSettings,
+}
+
+#[derive(Clone, Default)]
+pub struct PlayerClicks {
+    /// Sounds of every category, by [`ClickType::index`].
+    pub categories: Vec<Vec<SoundWrapper>>,
+    /// Layers of every category, by [`ClickType::index`].
+    pub layers: Vec<Vec<Layer>>,
+}
+
+fn read_clicks_in_directory(
+    src: &ClickpackSource,
+    dir: &Path,
+    report: &mut ClickpackReport,
+) -> Vec<SoundWrapper> {
+    let Some(entries) = src.read_dir(dir) else {
+        // log::warn!("can't find directory {dir:?}, skipping");
+        return vec![];
+    };
+    let files: Vec<PathBuf> = entries.into_iter().filter(|p| src.is_file(p)).collect();
+    let mut sounds = vec![];
+    for (path, sound) in files.iter().zip(src.load_sounds(&files)) {
+        match sound {
+            Ok(sound) => sounds.push(sound),
+            Err(e) => {
+                log::error!("failed to load '{path:?}': {e}");
+                report.undecodable_file(path, &e);
+            }
+        }
+    }
+    sounds
+}
+
+impl PlayerClicks {
+    fn load_from_subdirs(
+        &mut self,
+        src: &ClickpackSource,
+        tiers: &Tiers,
+        path: &Path,
+        report: &mut ClickpackReport,
+    ) {
+        let Some(entries) = src.read_dir(path) else {
+            log::warn!("failed to read directory {path:?}");
+            return;
+        };
+        for entry in entries {
+            self.load_from_dir(src, tiers, &entry, report)
+        }
+    }
+
+    // parses folders like "softclicks", "soft_clicks", "soft click", or the folders of
+    // custom tiers
+    fn load_from_dir(
+        &mut self,
+        src: &ClickpackSource,
+        tiers: &Tiers,
+        path: &Path,
+        report: &mut ClickpackReport,
+    ) {
+        log::debug!("trying to match directory {:?}", path);
+        if src.is_file(path) {
+            log::debug!("skipping matching file {:?}", path);
+            return;
+        }
+        let filename = path.file_name().unwrap().to_string_lossy();
+        let typ = tiers.category_from_folder_name(&filename);
+        let layer_typ = tiers.layer_from_folder_name(&filename);
+        if let Some(clicks) = typ.and_then(|typ| self.sounds_mut(typ)) {
+            log::debug!("directory {path:?} matched {typ:?}");
+            *clicks = read_clicks_in_directory(src, path, report);
+        } else if let Some(layers) = layer_typ.and_then(|typ| self.layers_mut(typ)) {
+            log::debug!("directory {path:?} is a layer of {layer_typ:?}");
+            layers.push(Layer {
+                path: path.to_path_buf(),
+                sounds: read_clicks_in_directory(src, path, report),
+                settings: LayerSettings::default(),
+            });
+        } else {
+            log::warn!(
+                "directory {:?} did not match any pattern, list it in {MANIFEST_FILENAME} to use it",
+                path
+            );
+            report.unmatched_folder(path);
+        }
+    }
+
+    pub fn num_sounds(&self) -> usize {
+        self.categories.iter().map(Vec::len).sum()
+    }
+
+    pub(crate) fn sounds_mut(&mut self, typ: ClickType) -> Option<&mut Vec<SoundWrapper>> {
+        let index = typ.index()?;
+        if self.categories.len() <= index {
+            self.categories.resize_with(index + 1, Vec::new);
+        }
+        Some(&mut self.categories[index])
+    }
+
+    pub(crate) fn layers_mut(&mut self, typ: ClickType) -> Option<&mut Vec<Layer>> {
+        let index = typ.index()?;
+        if self.layers.len() <= index {
+            self.layers.resize_with(index + 1, Vec::new);
+        }
+        Some(&mut self.layers[index])
+    }
+
+    pub fn layers(&self, typ: ClickType) -> &[Layer] {
+        typ.index()
+            .and_then(|i| self.layers.get(i))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn sounds(&self, typ: ClickType) -> &[SoundWrapper] {
+        typ.index()
+            .and_then(|i| self.categories.get(i))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Sounds of each category, by folder name.
+    pub fn folders<'a>(
+        &'a self,
+        tiers: &'a Tiers,
+    ) -> impl Iterator<Item = (&'a str, &'a [SoundWrapper])> {
+        tiers
+            .categories()
+            .map(|(typ, folder)| (folder, self.sounds(typ)))
+    }
+
+    /// Random sound of `click_type`, or of the closest category that has sounds.
+    ///
+    /// `recent` are the paths of sounds that were just played, newest last, which are
+    /// avoided if possible.
+    pub fn random_click(
+        &self,
+        rng: &mut fastrand::Rng,
+        tiers: &Tiers,
+        click_type: ClickType,
+        recent: &[PathBuf],
+    ) -> Option<&SoundWrapper> {
+        tiers
+            .preferred(click_type)
+            .find_map(|typ| pick_weighted(rng, self.sounds(typ), recent))
+    }
+
+    fn clear(&mut self) {
+        self.categories.clear();
+        self.layers.clear();
+    }
+}
+
+#[derive(Default)]
+pub struct Clickpack {
+    pub player1: PlayerClicks,
+    pub player2: PlayerClicks,
+    pub left1: PlayerClicks,
+    pub right1: PlayerClicks,
+    pub left2: PlayerClicks,
+    pub right2: PlayerClicks,
+    pub noise: Option<SoundWrapper>,
+    pub num_sounds: usize,
+    pub has_platformer_sounds: bool,
+    pub name: String,
+    pub path: PathBuf,
+    pub manifest: Option<ClickpackManifest>,
+    /// Problems found while loading.
+    pub report: ClickpackReport,
+    /// Where to keep loudness measurements and decoded sounds (`.dcd/cache` in the mod).
+    /// Nothing is cached if unset.
+    pub cache_dir: Option<PathBuf>,
+    /// Categories to load sounds for. Set before loading, the default is the four tiers
+    /// from hardclicks to microclicks.
+    pub tiers: Tiers,
+    /// Measure loudness while loading, needed for [`VolumeSettings::normalize`]. Call
+    /// [`Clickpack::measure_loudness`] if it's turned on later.
+    pub analyze_loudness: bool,
+}
+
+impl std::ops::Index<usize> for Clickpack {
+    type Output = PlayerClicks;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.player1,
+            1 => &self.player2,
+            2 => &self.left1,
+            3 => &self.right1,
+            4 => &self.left2,
+            5 => &self.right2,
+            _ => panic!("invalid index"),
+        }
+    }
+}
+
+impl std::ops::IndexMut<usize> for Clickpack {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        match index {
+            0 => &mut self.player1,
+            1 => &mut self.player2,
+            2 => &mut self.left1,
+            3 => &mut self.right1,
+            4 => &mut self.left2,
+            5 => &mut self.right2,
+            _ => panic!("invalid index"),
+        }
+    }
+}
+
+/// Player and button of each of [`Clickpack::players`].
+pub(crate) const PLAYER_BUTTONS: [(bool, Button); 6] = [
+    (false, Button::Jump),
+    (true, Button::Jump),
+    (false, Button::Left),
+    (false, Button::Right),
+    (true, Button::Left),
+    (true, Button::Right),
+];
+
+/// Indices of the players to pick sounds from for `button`, the button's own first.
+const fn source_order(player2: bool, button: Button) -> [usize; 6] {
+    // player1, player2, left1, right1, left2, right2
+    match (button, player2) {
+        (Button::Jump, false) => [0, 1, 2, 3, 4, 5],
+        (Button::Jump, true) => [1, 0, 4, 5, 2, 3],
+        (Button::Left, false) => [2, 3, 0, 4, 5, 1],
+        (Button::Left, true) => [4, 5, 1, 2, 3, 0],
+        (Button::Right, false) => [3, 2, 0, 5, 4, 1],
+        (Button::Right, true) => [5, 4, 1, 3, 2, 0],
+    }
+}
+
+pub(crate) const CLICKPACK_DIRNAMES: [&str; 6] = ["player1", "player2", "left1", "left2", "right1", "right2"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LoadClickpackFor {
+    #[default]
+    All,
+    Player1,
+    Player2,
+    Left1,
+    Left2,
+    Right1,
+    Right2,
+}
+
+impl LoadClickpackFor {
+    const fn to_index(self) -> usize {
+        match self {
+            Self::All => 0,
+            Self::Player1 => 1,
+            Self::Player2 => 2,
+            Self::Left1 => 3,
+            Self::Left2 => 4,
+            Self::Right1 => 5,
+            Self::Right2 => 6,
+        }
+    }
+}
+
+fn find_noise_file(src: &ClickpackSource, dir: &Path) -> Option<PathBuf> {
+    let entries = src.read_dir(dir)?;
+    for path in entries {
+        let filename = path.file_name().unwrap().to_str().unwrap();
+        // if it's a noise*, etc file we should try to load it
+        if src.is_file(&path)
+            && (filename.starts_with("noise")
+                || filename.starts_with("whitenoise")
+                || filename.starts_with("pcnoise")
+                || filename.starts_with("background"))
+        {
+            return Some(path);
+        }
+    }
+    None
+}
+
+impl Clickpack {
+    fn load_noise(&mut self, src: &ClickpackSource, dir: &Path) {
+        let Some(path) = find_noise_file(src, dir) else {
+            return;
+        };
+        // try to load noise
+        self.noise = src.load_sound(/*self.system*/ &path).ok();
+    }
+
+    fn load_from_folder_names(
+        &mut self,
+        src: &ClickpackSource,
+        clickpack_dir: &Path,
+        load_for: LoadClickpackFor,
+        report: &mut ClickpackReport,
+    ) {
+        // this is probably the most confusing code i've ever written
+        let tiers = self.tiers.clone();
+        let mut has_cleared = false;
+        for (i, dir) in CLICKPACK_DIRNAMES.iter().enumerate() {
+            let sound_idx = if load_for != LoadClickpackFor::All {
+                load_for.to_index() - 1 // because All is the first variant
+            } else {
+                i
+            };
+            log::info!("sound index {sound_idx}");
+            let sounds = &mut self[sound_idx];
+            if load_for != LoadClickpackFor::All && !has_cleared {
+                log::debug!("clearing sound list for {load_for:?}");
+                sounds.clear();
+                has_cleared = true;
+            }
+
+            let mut path = clickpack_dir.to_path_buf();
+            path.push(dir);
+            log::debug!("loading from dir {path:?}");
+
+            sounds.load_from_subdirs(src, &tiers, &path, report);
+            if load_for != LoadClickpackFor::All && sounds.num_sounds() == 0 {
+                log::warn!("directory {dir:?} was not found or has no clicks, assuming there isn't a subdirectory");
+                sounds.load_from_subdirs(src, &tiers, clickpack_dir, report);
+            }
+
+            // try to load noise from the sound directories
+            if self.noise.is_none() {
+                self.load_noise(src, &path);
+            }
+        }
+
+        if !self.has_clicks() {
+            log::warn!("folders {CLICKPACK_DIRNAMES:?} were not found in the clickpack, assuming there is only one player");
+            self[0].load_from_subdirs(src, &tiers, clickpack_dir, report);
+        }
+    }
+
+    fn load_from_manifest(
+        &mut self,
+        src: &ClickpackSource,
+        clickpack_dir: &Path,
+        manifest: &ClickpackManifest,
+        load_for: LoadClickpackFor,
+        report: &mut ClickpackReport,
+    ) -> Result<()> {
+        if load_for != LoadClickpackFor::All {
+            self[load_for.to_index() - 1].clear();
+        }
+        for sound in &manifest.sounds {
+            let typ = self.tiers.category_from_folder_name(&sound.typ);
+            let typ = typ.with_context(|| {
+                format!("unknown sound type {:?} in {MANIFEST_FILENAME}", sound.typ)
+            })?;
+            let player = self
+                .players()
+                .iter()
+                .position(|(name, _)| *name == sound.player);
+            let Some(player) = player else {
+                anyhow::bail!("unknown player {:?} in {MANIFEST_FILENAME}", sound.player);
+            };
+            let clicks = if load_for == LoadClickpackFor::All {
+                &mut self[player]
+            } else {
+                // like with folders, everything goes to the selected player
+                &mut self[load_for.to_index() - 1]
+            };
+
+            let path = clickpack_dir.join(&sound.path);
+            let mut sounds = if src.is_file(&path) {
+                match src.load_sound(&path) {
+                    Ok(sound) => vec![sound],
+                    Err(e) => {
+                        log::error!("failed to load '{path:?}': {e}");
+                        report.undecodable_file(&path, &e);
+                        vec![]
+                    }
+                }
+            } else {
+                read_clicks_in_directory(src, &path, report)
+            };
+            if sounds.is_empty() {
+                log::warn!("{path:?} from {MANIFEST_FILENAME} has no sounds");
+            }
+            if let Some(weight) = sound.weight {
+                sounds.iter_mut().for_each(|s| s.weight = weight);
+            }
+            if let Some(settings) = &sound.layer {
+                if let Some(layers) = clicks.layers_mut(typ) {
+                    layers.push(Layer {
+                        path,
+                        sounds,
+                        settings: settings.clone(),
+                    });
+                }
+            } else if let Some(list) = clicks.sounds_mut(typ) {
+                list.extend(sounds);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set weights of sounds listed by their path relative to `clickpack_dir`.
+    fn apply_weights(&mut self, clickpack_dir: &Path, weights: &BTreeMap<PathBuf, f64>) {
+        if weights.is_empty() {
+            return;
+        }
+        for i in 0..6 {
+            let clicks = &mut self[i];
+            let layer_sounds = clicks.layers.iter_mut().flatten();
+            let sounds = clicks
+                .categories
+                .iter_mut()
+                .flatten()
+                .chain(layer_sounds.flat_map(|layer| layer.sounds.iter_mut()));
+            for sound in sounds {
+                let path = sound
+                    .path
+                    .strip_prefix(clickpack_dir)
+                    .unwrap_or(&sound.path);
+                if let Some(weight) = weights.get(path) {
+                    sound.weight = *weight;
+                }
+            }
+        }
+    }
+
+    /// Set volume and jitter of layers listed by their path relative to `clickpack_dir`.
+    fn apply_layer_settings(
+        &mut self,
+        clickpack_dir: &Path,
+        settings: &BTreeMap<PathBuf, LayerSettings>,
+    ) {
+        for i in 0..6 {
+            for layer in self[i].layers.iter_mut().flatten() {
+                let path = layer
+                    .path
+                    .strip_prefix(clickpack_dir)
+                    .unwrap_or(&layer.path);
+                if let Some(settings) = settings.get(path) {
+                    layer.settings = settings.clone();
+                }
+            }
+        }
+    }
+
+    /// Measure the loudness of every sound that wasn't measured yet and set the gain that
+    /// brings each folder, layers included, to the same level, see
+    /// [`SoundWrapper::normalize_gain`].
+    pub fn measure_loudness(&mut self) {
+        let mut cache = LoudnessCache::load(self.cache_dir.as_deref(), &self.path);
+        let mut measure = |sounds: &mut [SoundWrapper]| {
+            for sound in sounds.iter_mut() {
+                match sound.loudness {
+                    Some(_) => cache.keep(&sound.sound),
+                    None => sound.loudness = Some(cache.measure(&sound.sound)),
+                }
+            }
+            let gain = loudness::folder_gain(sounds).unwrap_or(1.0);
+            sounds.iter_mut().for_each(|s| s.norm_gain = gain);
+        };
+        for i in 0..6 {
+            let clicks = &mut self[i];
+            for sounds in clicks.categories.iter_mut() {
+                measure(sounds);
+            }
+            for layer in clicks.layers.iter_mut().flatten() {
+                measure(&mut layer.sounds);
+            }
+        }
+        cache.save();
+    }
+
+    pub fn load_from_path(
+        &mut self,
+        clickpack_dir: &Path,
+        load_for: LoadClickpackFor,
+    ) -> Result<()> {
+        log::info!("loading clickpack from path {clickpack_dir:?} for {load_for:?}");
+        let src = ClickpackSource::open(clickpack_dir, self.cache_dir.as_deref(), &self.tiers)?;
+        self.path = clickpack_dir.to_path_buf();
+        self.name = clickpack_dir
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string();
+
+        if load_for != LoadClickpackFor::All {
+            self.noise = None;
+        }
+
+        // keep what was found while loading the other players
+        let mut report = if load_for == LoadClickpackFor::All {
+            ClickpackReport::default()
+        } else {
+            std::mem::take(&mut self.report)
+        };
+
+        let manifest = ClickpackManifest::load(&src, clickpack_dir)
+            .map_err(|e| {
+                log::error!("{e:#}, matching folders by name instead");
+                report.invalid_manifest(&e);
+            })
+            .ok()
+            .flatten();
+        if let Some(noise) = manifest.as_ref().and_then(|m| m.noise.as_ref()) {
+            let path = clickpack_dir.join(noise);
+            self.noise = src
+                .load_sound(&path)
+                .map_err(|e| log::error!("failed to load noise {path:?}: {e}"))
+                .ok();
+        }
+        let mapped = manifest.as_ref().filter(|m| !m.sounds.is_empty());
+        let from_manifest = mapped.map(|manifest| {
+            self.load_from_manifest(&src, clickpack_dir, manifest, load_for, &mut report)
+        });
+        match from_manifest {
+            Some(Ok(())) => {}
+            Some(Err(e)) => {
+                log::error!("{e:#}, matching folders by name instead");
+                report.invalid_manifest(&e);
+                if load_for == LoadClickpackFor::All {
+                    (0..6).for_each(|i| self[i].clear());
+                }
+                self.load_from_folder_names(&src, clickpack_dir, load_for, &mut report);
+            }
+            None => self.load_from_folder_names(&src, clickpack_dir, load_for, &mut report),
+        }
+        if let Some(manifest) = &manifest {
+            self.apply_weights(clickpack_dir, &manifest.weights);
+            self.apply_layer_settings(clickpack_dir, &manifest.layers);
+        }
+        if load_for == LoadClickpackFor::All {
+            self.manifest = manifest;
+        }
+
+        // try to load noise from the root clickpack dir
+        if self.noise.is_none() {
+            self.load_noise(&src, clickpack_dir);
+        }
+        src.evict_cache();
+
+        self.num_sounds = self.num_sounds();
+        log::info!(
+            "amount of sounds in clickpack {clickpack_dir:?}: {}",
+            self.num_sounds
+        );
+        for (name, clicks) in self.players() {
+            log::info!("    {name}: {} sounds", clicks.num_sounds());
+            for (folder, sounds) in clicks.folders(&self.tiers) {
+                log::info!(
+                    "        {folder}: {} sounds{}",
+                    sounds.len(),
+                    if !sounds.is_empty() { " <<<<<<<" } else { "" }
+                );
+            }
+            for layer in clicks.layers.iter().flatten() {
+                log::info!(
+                    "        layer {:?}: {} sounds",
+                    layer.path,
+                    layer.sounds.len()
+                );
+            }
+        }
+        self.has_platformer_sounds = self.left1.num_sounds() != 0
+            || self.right1.num_sounds() != 0
+            || self.left2.num_sounds() != 0
+            || self.right2.num_sounds() != 0;
+
+        if self.analyze_loudness {
+            self.measure_loudness();
+        }
+        report.check_sounds(self);
+        self.report = report;
+
+        if self.has_clicks() {
+            Ok(())
+        } else {
+            anyhow::bail!("no clicks found in clickpack, did you select the correct folder?")
+        }
+    }
+
+    /// Sounds of each player/platformer button, by folder name.
+    pub fn players(&self) -> [(&'static str, &PlayerClicks); 6] {
+        [
+            ("player1", &self.player1),
+            ("player2", &self.player2),
+            ("left1", &self.left1),
+            ("right1", &self.right1),
+            ("left2", &self.left2),
+            ("right2", &self.right2),
+        ]
+    }
+
+    fn has_clicks(&self) -> bool {
+        self.player1.num_sounds() != 0
+            || self.player2.num_sounds() != 0
+            || self.left1.num_sounds() != 0
+            || self.right1.num_sounds() != 0
+            || self.left2.num_sounds() != 0
+            || self.right2.num_sounds() != 0
+    }
+
+    pub fn num_sounds(&self) -> usize {
+        self.player1.num_sounds()
+            + self.player2.num_sounds()
+            + self.left1.num_sounds()
+            + self.right1.num_sounds()
+            + self.left2.num_sounds()
+            + self.right2.num_sounds()
+    }
+
+    /// Player (index of [`Clickpack::players`]) and category a sound for `typ` is taken
+    /// from: the first player in the button's fallback order with a sound in any of the
+    /// [`Tiers::preferred`] categories. Sounds with a weight of 0 don't count.
+    pub fn lookup(
+        &self,
+        typ: ClickType,
+        player2: bool,
+        button: Button,
+    ) -> Option<(usize, ClickType)> {
+        source_order(player2, button).into_iter().find_map(|i| {
+            let typ = self
+                .tiers
+                .preferred(typ)
+                .find(|t| self[i].sounds(*t).iter().any(|s| s.weight > 0.0))?;
+            Some((i, typ))
+        })
+    }
+
+    /// Random sound for `typ` from the player and category [`Clickpack::lookup`] picks,
+    /// the same ones the report and the layers use. `None` if the clickpack has no sounds.
+    pub fn get_random_click(
+        &mut self,
+        rng: &mut fastrand::Rng,
+        typ: ClickType,
+        player2: bool,
+        button: Button,
+        recent: &[PathBuf],
+    ) -> Option<SoundWrapper> {
+        let (player, typ) = self.lookup(typ, player2, button)?;
+        pick_weighted(rng, self[player].sounds(typ), recent).cloned()
+    }
+
+    // New method for advanced sound filtering
+    pub fn get_filtered_click(
+        &mut self,
+        rng: &mut fastrand::Rng,
+        typ: ClickType,
+        player2: bool,
+        button: Button,
+        filter_criteria: &SoundFilterCriteria,
+        recent: &[PathBuf],
+    ) -> Option<SoundWrapper> {
+        let (player, typ) = self.lookup(typ, player2, button)?;
+        let sounds = self[player].sounds(typ);
+        pick_weighted_matching(rng, sounds, recent, |s| filter_criteria.matches(s))
+            // Fallback to basic selection if no filtered sounds found
+            .or_else(|| pick_weighted(rng, sounds, recent))
+            .cloned()
+    }
+
+    /// A random sound from every layer of the category a click of `typ` is taken from,
+    /// with the layer's settings.
+    pub fn random_layers(
+        &self,
+        rng: &mut fastrand::Rng,
+        typ: ClickType,
+        player2: bool,
+        button: Button,
+    ) -> Vec<(SoundWrapper, LayerSettings)> {
+        // the category the click is taken from, see `get_random_click`
+        let Some((player, typ)) = self.lookup(typ, player2, button) else {
+            return vec![];
+        };
+        self[player]
+            .layers(typ)
+            .iter()
+            .filter_map(|layer| {
+                let sound = pick_weighted(rng, &layer.sounds, &[])?;
+                Some((sound.clone(), layer.settings.clone()))
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub const fn has_noise(&self) -> bool {
+        self.noise.is_some()
+    }
+}
+
+// Enhanced sound filtering criteria for advanced audio selection
+#[derive(Debug, Clone)]
+pub struct SoundFilterCriteria {
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    pub preferred_quality: Option<SoundQuality>,
+    pub exclude_duplicates: bool,
+    pub context_aware: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SoundQuality {
+    Low,
+    Medium,
+    High,
+    Ultra,
+}
+
+impl SoundFilterCriteria {
+    pub fn new() -> Self {
+        Self {
+            min_duration: None,
+            max_duration: None,
+            preferred_quality: None,
+            exclude_duplicates: false,
+            context_aware: true,
+        }
+    }
+
+    pub fn with_duration_range(mut self, min: f64, max: f64) -> Self {
+        self.min_duration = Some(min);
+        self.max_duration = Some(max);
+        self
+    }
+
+    pub fn with_quality(mut self, quality: SoundQuality) -> Self {
+        self.preferred_quality = Some(quality);
+        self
+    }
+
+    pub fn matches(&self, sound: &SoundWrapper) -> bool {
+        let duration = sound.sound.frames.len() as f64 / 48000.0; // Assuming 48kHz sample rate
+
+        // Check duration constraints
+        if let Some(min_dur) = self.min_duration {
+            if duration < min_dur {
+                return false;
+            }
+        }
+
+        if let Some(max_dur) = self.max_duration {
+            if duration > max_dur {
+                return false;
+            }
+        }
+
+        // Check quality preferences
+        if let Some(quality) = &self.preferred_quality {
+            let sound_quality = self.assess_sound_quality(sound);
+            if sound_quality != *quality {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn assess_sound_quality(&self, sound: &SoundWrapper) -> SoundQuality {
+        let duration = sound.sound.frames.len() as f64;
+        let sample_rate = 48000.0; // Assuming 48kHz
+        let actual_duration = duration / sample_rate;
+
+        // Assess quality based on duration and sample characteristics
+        if actual_duration > 0.1 && duration > 10000.0 {
+            SoundQuality::Ultra
+        } else if actual_duration > 0.05 && duration > 5000.0 {
+            SoundQuality::High
+        } else if actual_duration > 0.02 && duration > 2000.0 {
+            SoundQuality::Medium
+        } else {
+            SoundQuality::Low
+        }
+    }
+}
+
+impl Default for SoundFilterCriteria {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittyaudio::Frame;
+
+    fn sound(path: &str, weight: f64) -> SoundWrapper {
+        SoundWrapper {
+            sound: Sound::from_frames(44100, &[Frame::default(); 4]),
+            path: PathBuf::from(path),
+            weight,
+            loudness: None,
+            norm_gain: 1.0,
+            onset: 0,
+        }
+    }
+
+    #[test]
+    fn weight_from_suffix() {
+        assert_eq!(weight_from_filename(Path::new("a/click@2.wav")), Some(2.0));
+        assert_eq!(weight_from_filename(Path::new("click@0.5.ogg")), Some(0.5));
+        assert_eq!(weight_from_filename(Path::new("click.wav")), None);
+        assert_eq!(weight_from_filename(Path::new("click@-1.wav")), None);
+    }
+
+    #[test]
+    fn zero_weight_is_never_picked() {
+        let sounds = [sound("a", 1.0), sound("b", 0.0)];
+        let mut rng = fastrand::Rng::with_seed(1);
+        for _ in 0..100 {
+            let picked = pick_weighted(&mut rng, &sounds, &[]).unwrap();
+            assert_eq!(picked.path, Path::new("a"));
+        }
+    }
+
+    #[test]
+    fn recent_sounds_are_avoided() {
+        let sounds = [sound("a", 1.0), sound("b", 1.0), sound("c", 1.0)];
+        let mut rng = fastrand::Rng::with_seed(1);
+        let recent = [PathBuf::from("a"), PathBuf::from("b")];
+        for _ in 0..100 {
+            let picked = pick_weighted(&mut rng, &sounds, &recent).unwrap();
+            assert_eq!(picked.path, Path::new("c"));
+        }
+
+        // with more recent sounds than can be avoided, only the newest are skipped
+        let recent = [PathBuf::from("c"), PathBuf::from("a"), PathBuf::from("b")];
+        let picked = pick_weighted(&mut rng, &sounds[..2], &recent).unwrap();
+        assert_eq!(picked.path, Path::new("a"));
+    }
+
+    #[test]
+    fn zero_weight_is_never_repeated() {
+        let sounds = [sound("a", 1.0), sound("b", 0.0)];
+        let mut rng = fastrand::Rng::with_seed(1);
+        // avoiding the last sound must not fall back to the silenced one
+        let recent = [PathBuf::from("a")];
+        for _ in 0..100 {
+            let picked = pick_weighted(&mut rng, &sounds, &recent).unwrap();
+            assert_eq!(picked.path, Path::new("a"));
+        }
+        assert!(pick_weighted(&mut rng, &sounds[1..], &[]).is_none());
+    }
+
+    #[test]
+    fn layers_follow_fallback() {
+        let mut clickpack = Clickpack::default();
+        let clicks = &mut clickpack.player1;
+        *clicks.sounds_mut(ClickType::Click(1)).unwrap() = vec![sound("clicks/1", 1.0)];
+        clicks.layers_mut(ClickType::Click(1)).unwrap().push(Layer {
+            path: PathBuf::from("clicks_layer"),
+            sounds: vec![sound("clicks_layer/1", 1.0)],
+            settings: LayerSettings::default(),
+        });
+        let mut rng = fastrand::Rng::with_seed(1);
+        // softclicks fall back to clicks, so they get the clicks layer too
+        for typ in [ClickType::Click(1), ClickType::Click(2)] {
+            let layers = clickpack.random_layers(&mut rng, typ, false, Button::Jump);
+            assert_eq!(layers.len(), 1);
+            assert_eq!(layers[0].0.path, Path::new("clicks_layer/1"));
+        }
+    }
+
+    #[test]
+    fn random_click_falls_back_to_other_players() {
+        let mut clickpack = Clickpack::default();
+        let mut rng = fastrand::Rng::with_seed(1);
+        let typ = ClickType::Click(1);
+        assert!(clickpack
+            .get_random_click(&mut rng, typ, true, Button::Left, &[])
+            .is_none());
+        *clickpack.player1.sounds_mut(ClickType::Release(0)).unwrap() =
+            vec![sound("player1/hardreleases/1", 1.0)];
+        let click = clickpack
+            .get_random_click(&mut rng, typ, true, Button::Left, &[])
+            .unwrap();
+        assert_eq!(click.path, Path::new("player1/hardreleases/1"));
+    }
+}
@@ -0,0 +1,223 @@
+use crate::{
+    clickpack::{LoadClickpackFor, PanSettings, Pitch, Timings, VolumeSettings},
+    dsp::DspSettings,
+    engine::{ClickSettings, IgnoredClickTypes, TimingOverrides},
+    tiers::{Tier, Tiers},
+    timing::TimeUnit,
+};
+use serde::{Deserialize, Serialize};
+
+const fn true_value() -> bool {
+    true
+}
+
+const fn float_one() -> f64 {
+    1.0
+}
+
+const fn death_release_delay_default() -> f64 {
+    0.001
+}
+
+const fn death_release_delay_offset_default() -> f64 {
+    0.13
+}
+
+/// Settings that decide what gets played, shared by every front end.
+///
+/// Front ends keep their own UI settings next to this one in `.dcd/config.json`.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Config {
+    pub pitch_enabled: bool,
+    pub pitch: Pitch,
+    pub timings: Timings,
+    /// Timings for single buttons and players, e.g. longer ones for platformer holds.
+    #[serde(default = "TimingOverrides::default")]
+    pub timing_overrides: TimingOverrides,
+    /// Custom click categories instead of hardclicks to microclicks, see [`Tiers`].
+    #[serde(default)]
+    pub tiers: Option<Vec<Tier>>,
+    pub volume_settings: VolumeSettings,
+    /// Where each player and platformer button is heard in the stereo field.
+    #[serde(default = "PanSettings::default")]
+    pub pan: PanSettings,
+    #[serde(default = "true_value")]
+    pub enabled: bool,
+    #[serde(default = "bool::default")]
+    pub play_noise: bool,
+    #[serde(default = "float_one")]
+    pub noise_volume: f64,
+    #[serde(default = "bool::default")]
+    pub force_playing_platformer: bool,
+    #[serde(default = "bool::default")]
+    pub cut_sounds: bool,
+    #[serde(default = "bool::default")]
+    pub cut_by_releases: bool,
+    #[serde(default = "float_one")]
+    pub click_speedhack: f64,
+    #[serde(default = "float_one")]
+    pub noise_speedhack: f64,
+    #[serde(default = "LoadClickpackFor::default")]
+    pub load_clickpack_for: LoadClickpackFor,
+    #[serde(default = "bool::default")]
+    pub decouple_platformer: bool,
+    #[serde(default = "true_value")]
+    pub release_buttons_on_death: bool,
+    #[serde(default = "death_release_delay_default")]
+    pub death_release_delay: f64,
+    #[serde(default = "death_release_delay_offset_default")]
+    pub death_release_delay_offset: f64,
+    #[serde(default = "bool::default")]
+    pub death_release_delay_neg: bool,
+    #[serde(default = "bool::default")]
+    pub force_player2_sounds: bool,
+    #[serde(default = "bool::default")]
+    pub play_noise_when_disabled: bool,
+    #[serde(default = "IgnoredClickTypes::default")]
+    pub ignored_click_types: IgnoredClickTypes,
+    #[serde(default = "bool::default")]
+    pub use_ingame_time: bool,
+    #[serde(default = "bool::default")]
+    pub advanced_sound_filtering: bool,
+    /// Makes click selection, pitch and volume variation reproducible.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Don't repeat any of the last N sounds, 0 to allow repeats.
+    #[serde(default = "usize::default")]
+    pub avoid_last: usize,
+    #[serde(default = "bool::default")]
+    pub trim_silence: bool,
+    #[serde(default = "true_value")]
+    pub play_layers: bool,
+    /// EQ, compressor and limiter on the mixed output.
+    #[serde(default = "DspSettings::default")]
+    pub dsp: DspSettings,
+    /// Unit of the timings, tier thresholds and death release delays.
+    #[serde(default)]
+    pub timing_unit: TimeUnit,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            pitch_enabled: true,
+            pitch: Pitch::default(),
+            timings: Timings::default(),
+            timing_overrides: TimingOverrides::default(),
+            tiers: None,
+            volume_settings: VolumeSettings::default(),
+            pan: PanSettings::default(),
+            enabled: true,
+            play_noise: false,
+            noise_volume: 1.0,
+            force_playing_platformer: false,
+            cut_sounds: false,
+            cut_by_releases: false,
+            click_speedhack: 1.0,
+            noise_speedhack: 1.0,
+            load_clickpack_for: LoadClickpackFor::All,
+            decouple_platformer: false,
+            release_buttons_on_death: true,
+            death_release_delay: death_release_delay_default(),
+            death_release_delay_offset: death_release_delay_offset_default(),
+            death_release_delay_neg: false,
+            force_player2_sounds: false,
+            play_noise_when_disabled: false,
+            ignored_click_types: IgnoredClickTypes::default(),
+            use_ingame_time: false,
+            advanced_sound_filtering: false,
+            seed: None,
+            avoid_last: 0,
+            trim_silence: false,
+            play_layers: true,
+            dsp: DspSettings::default(),
+            timing_unit: TimeUnit::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Settings used by the [`ClickEngine`](crate::engine::ClickEngine) for every action,
+    /// for a game running at `tps` ticks per second.
+    pub fn click_settings(&self, tps: f64) -> ClickSettings {
+        ClickSettings {
+            pitch_enabled: self.pitch_enabled,
+            pitch: self.pitch.clone(),
+            timings: self.timings.clone(),
+            timing_overrides: self.timing_overrides.clone(),
+            tiers: self.tiers.clone(),
+            volume_settings: self.volume_settings.clone(),
+            pan: self.pan.clone(),
+            click_speedhack: self.click_speedhack,
+            force_playing_platformer: self.force_playing_platformer,
+            decouple_platformer: self.decouple_platformer,
+            ignored_click_types: self.ignored_click_types.clone(),
+            advanced_sound_filtering: self.advanced_sound_filtering,
+            seed: self.seed,
+            avoid_last: self.avoid_last,
+            trim_silence: self.trim_silence,
+            play_layers: self.play_layers,
+            timing_unit: self.timing_unit,
+            tps,
+        }
+    }
+
+    /// Categories clickpacks are loaded and played with.
+    pub fn tiers(&self) -> Tiers {
+        match &self.tiers {
+            Some(tiers) => Tiers::new(tiers.clone()),
+            None => Tiers::preset(&self.timings),
+        }
+    }
+
+    /// Switch the timings, tier thresholds and death release delays to `unit`,
+    /// converting them at `tps` ticks per second so they stay the same length.
+    pub fn set_timing_unit(&mut self, unit: TimeUnit, tps: f64) {
+        let from = self.timing_unit;
+        let convert =
+            |value: &mut f64| *value = unit.secs_to_unit(from.unit_to_secs(*value, tps), tps);
+        let overrides = &mut self.timing_overrides;
+        for timings in [
+            &mut overrides.jump,
+            &mut overrides.left,
+            &mut overrides.right,
+        ]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .chain([&mut self.timings])
+        {
+            convert(&mut timings.hard);
+            convert(&mut timings.regular);
+            convert(&mut timings.soft);
+        }
+        for tier in self.tiers.iter_mut().flatten() {
+            convert(&mut tier.threshold);
+        }
+        convert(&mut self.death_release_delay);
+        convert(&mut self.death_release_delay_offset);
+        self.timing_unit = unit;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_unit_conversion() {
+        let mut config = Config::default();
+        config.set_timing_unit(TimeUnit::Frames, 240.0);
+        assert_eq!(config.timing_unit, TimeUnit::Frames);
+        assert!((config.timings.regular - 36.0).abs() < 1e-9);
+        assert!((config.timings.soft - 6.0).abs() < 1e-9);
+        assert!((config.death_release_delay - 0.24).abs() < 1e-9);
+        // thresholds in ticks are compared at the game's tick rate
+        let settings = config.click_settings(60.0);
+        assert!((settings.in_timing_unit(0.5) - 30.0).abs() < 1e-9);
+
+        config.set_timing_unit(TimeUnit::Seconds, 240.0);
+        assert!((config.timings.hard - 2.0).abs() < 1e-9);
+        assert!((config.timings.regular - 0.15).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,620 @@
+//! Processing on the click bus: a parametric EQ, reverb, a compressor and a brickwall
+//! limiter, in that order. Live playback runs every output frame through a [`DspChain`] and
+//! offline rendering runs the finished mix through the same one.
+
+use crate::{
+    loudness::db_to_gain,
+    reverb::{ImpulseResponse, Reverb, ReverbSettings},
+};
+use kittyaudio::Frame;
+use serde::{Deserialize, Serialize};
+use std::{f64::consts::PI, sync::Arc};
+
+/// EQ presets. `Custom` uses the bands from [`EqSettings::bands`].
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SoundProfile {
+    Bass,
+    #[default]
+    Balanced,
+    Treble,
+    Custom,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FilterKind {
+    LowShelf,
+    Peak,
+    HighShelf,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct EqBand {
+    pub kind: FilterKind,
+    /// Center or corner frequency in Hz.
+    pub freq: f64,
+    pub gain_db: f64,
+    pub q: f64,
+}
+
+impl EqBand {
+    const fn new(kind: FilterKind, freq: f64, gain_db: f64) -> Self {
+        Self {
+            kind,
+            freq,
+            gain_db,
+            q: 0.707,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct EqSettings {
+    pub enabled: bool,
+    pub profile: SoundProfile,
+    /// Bands of the `Custom` profile.
+    pub bands: Vec<EqBand>,
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            profile: SoundProfile::Balanced,
+            bands: vec![
+                EqBand::new(FilterKind::LowShelf, 150.0, 0.0),
+                EqBand::new(FilterKind::Peak, 1000.0, 0.0),
+                EqBand::new(FilterKind::HighShelf, 6000.0, 0.0),
+            ],
+        }
+    }
+}
+
+impl EqSettings {
+    /// Bands of the selected profile, empty if the EQ is off.
+    pub fn active_bands(&self) -> Vec<EqBand> {
+        if !self.enabled {
+            return vec![];
+        }
+        match self.profile {
+            SoundProfile::Bass => vec![
+                EqBand::new(FilterKind::LowShelf, 150.0, 4.0),
+                EqBand::new(FilterKind::HighShelf, 6000.0, -2.0),
+            ],
+            SoundProfile::Balanced => vec![],
+            SoundProfile::Treble => vec![
+                EqBand::new(FilterKind::LowShelf, 150.0, -2.0),
+                EqBand::new(FilterKind::HighShelf, 5000.0, 4.0),
+            ],
+            SoundProfile::Custom => self.bands.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct CompressorSettings {
+    pub enabled: bool,
+    pub threshold_db: f64,
+    pub ratio: f64,
+    pub attack_ms: f64,
+    pub release_ms: f64,
+    /// Gain added after compression.
+    pub makeup_db: f64,
+}
+
+impl Default for CompressorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: -18.0,
+            ratio: 4.0,
+            attack_ms: 5.0,
+            release_ms: 80.0,
+            makeup_db: 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct LimiterSettings {
+    pub enabled: bool,
+    /// Highest sample level let through.
+    pub ceiling_db: f64,
+    pub release_ms: f64,
+}
+
+impl Default for LimiterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ceiling_db: -0.3,
+            release_ms: 50.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, Default)]
+#[serde(default)]
+pub struct DspSettings {
+    pub eq: EqSettings,
+    pub reverb: ReverbSettings,
+    pub compressor: CompressorSettings,
+    pub limiter: LimiterSettings,
+}
+
+impl DspSettings {
+    /// Whether any stage changes the signal.
+    pub fn is_active(&self) -> bool {
+        !self.eq.active_bands().is_empty()
+            || self.reverb.enabled
+            || self.compressor.enabled
+            || self.limiter.enabled
+    }
+
+    /// Check the settings, returning a description of every problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut check = |ok: bool, msg: String| {
+            if !ok {
+                problems.push(msg);
+            }
+        };
+        for band in &self.eq.bands {
+            check(
+                band.freq > 0.0 && band.q > 0.0,
+                format!(
+                    "EQ band at {} Hz must have a positive frequency and Q ({})",
+                    band.freq, band.q
+                ),
+            );
+        }
+        let r = &self.reverb;
+        check(
+            (0.0..=1.0).contains(&r.mix)
+                && (0.0..=1.0).contains(&r.room_size)
+                && (0.0..=1.0).contains(&r.damping),
+            format!(
+                "reverb mix ({}), room size ({}) and damping ({}) must be between 0 and 1",
+                r.mix, r.room_size, r.damping
+            ),
+        );
+        let c = &self.compressor;
+        check(
+            c.ratio >= 1.0,
+            format!("compressor ratio ({}) must be at least 1", c.ratio),
+        );
+        check(
+            c.attack_ms >= 0.0 && c.release_ms >= 0.0,
+            format!(
+                "compressor attack ({}) and release ({}) can't be negative",
+                c.attack_ms, c.release_ms
+            ),
+        );
+        check(
+            self.limiter.ceiling_db <= 0.0,
+            format!(
+                "limiter ceiling ({} dB) must not be above 0 dB",
+                self.limiter.ceiling_db
+            ),
+        );
+        check(
+            self.limiter.release_ms >= 0.0,
+            format!(
+                "limiter release ({}) can't be negative",
+                self.limiter.release_ms
+            ),
+        );
+        problems
+    }
+}
+
+/// Smoothing coefficient for a one-pole envelope with time constant `ms`.
+fn envelope_coef(ms: f64, sample_rate: u32) -> f64 {
+    if ms <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / (ms / 1000.0 * sample_rate as f64)).exp()
+}
+
+/// RBJ cookbook biquad, transposed direct form II with a state per channel.
+#[derive(Clone, Debug)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    state: [[f64; 2]; 2],
+}
+
+impl Biquad {
+    fn new(band: &EqBand, sample_rate: u32) -> Self {
+        let nyquist = sample_rate as f64 / 2.0;
+        let w0 = 2.0 * PI * band.freq.clamp(1.0, nyquist * 0.98) / sample_rate as f64;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * band.q.max(0.01));
+        let a = 10f64.powf(band.gain_db / 40.0);
+        let shelf = 2.0 * a.sqrt() * alpha;
+        let (b0, b1, b2, a0, a1, a2) = match band.kind {
+            FilterKind::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            FilterKind::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                (a + 1.0) + (a - 1.0) * cos + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - shelf,
+            ),
+            FilterKind::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                (a + 1.0) - (a - 1.0) * cos + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - shelf,
+            ),
+        };
+        Self {
+            b: [b0 / a0, b1 / a0, b2 / a0],
+            a: [a1 / a0, a2 / a0],
+            state: [[0.0; 2]; 2],
+        }
+    }
+
+    /// Switch to `band` without resetting the state.
+    fn set(&mut self, band: &EqBand, sample_rate: u32) {
+        let Self { b, a, .. } = Self::new(band, sample_rate);
+        (self.b, self.a) = (b, a);
+    }
+
+    fn process(&mut self, channel: usize, x: f64) -> f64 {
+        let s = &mut self.state[channel];
+        let y = self.b[0] * x + s[0];
+        s[0] = self.b[1] * x - self.a[0] * y + s[1];
+        s[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// Stereo-linked feed-forward compressor.
+#[derive(Clone, Debug)]
+struct Compressor {
+    settings: CompressorSettings,
+    attack: f64,
+    release: f64,
+    /// Current gain reduction in dB.
+    reduction: f64,
+}
+
+impl Compressor {
+    fn new(settings: &CompressorSettings, sample_rate: u32) -> Self {
+        Self {
+            settings: settings.clone(),
+            attack: envelope_coef(settings.attack_ms, sample_rate),
+            release: envelope_coef(settings.release_ms, sample_rate),
+            reduction: 0.0,
+        }
+    }
+
+    /// Switch to `settings`, keeping the current gain reduction.
+    fn set(&mut self, settings: &CompressorSettings, sample_rate: u32) {
+        *self = Self {
+            reduction: self.reduction,
+            ..Self::new(settings, sample_rate)
+        };
+    }
+
+    fn gain(&mut self, peak: f64) -> f64 {
+        let level_db = 20.0 * peak.max(1e-9).log10();
+        let over = level_db - self.settings.threshold_db;
+        let target = if over > 0.0 {
+            over * (1.0 - 1.0 / self.settings.ratio.max(1.0))
+        } else {
+            0.0
+        };
+        let coef = if target > self.reduction {
+            self.attack
+        } else {
+            self.release
+        };
+        self.reduction = target + coef * (self.reduction - target);
+        db_to_gain(self.settings.makeup_db - self.reduction)
+    }
+}
+
+/// Brickwall limiter without lookahead: the gain drops instantly to keep every sample
+/// under the ceiling and recovers over `release_ms`.
+#[derive(Clone, Debug)]
+struct Limiter {
+    ceiling: f64,
+    release: f64,
+    gain: f64,
+}
+
+impl Limiter {
+    fn new(settings: &LimiterSettings, sample_rate: u32) -> Self {
+        Self {
+            ceiling: db_to_gain(settings.ceiling_db.min(0.0)),
+            release: envelope_coef(settings.release_ms, sample_rate),
+            gain: 1.0,
+        }
+    }
+
+    /// Switch to `settings`, keeping the current gain.
+    fn set(&mut self, settings: &LimiterSettings, sample_rate: u32) {
+        *self = Self {
+            gain: self.gain,
+            ..Self::new(settings, sample_rate)
+        };
+    }
+
+    fn gain(&mut self, peak: f64) -> f64 {
+        let needed = if peak > self.ceiling {
+            self.ceiling / peak
+        } else {
+            1.0
+        };
+        self.gain = if needed < self.gain {
+            needed
+        } else {
+            needed + self.release * (self.gain - needed)
+        };
+        self.gain
+    }
+}
+
+/// EQ bands that change the sound, one filter each.
+fn eq_filters(settings: &DspSettings) -> Vec<EqBand> {
+    let mut bands = settings.eq.active_bands();
+    bands.retain(|band| band.gain_db != 0.0);
+    bands
+}
+
+/// A running instance of [`DspSettings`] at one sample rate.
+#[derive(Clone, Debug)]
+pub struct DspChain {
+    settings: DspSettings,
+    sample_rate: u32,
+    filters: Vec<Biquad>,
+    reverb: Option<Reverb>,
+    /// Kept so that changing other settings doesn't load it again.
+    ir: Option<Arc<ImpulseResponse>>,
+    compressor: Option<Compressor>,
+    limiter: Option<Limiter>,
+}
+
+impl DspChain {
+    pub fn new(settings: &DspSettings, sample_rate: u32) -> Self {
+        Self::build(settings, sample_rate, None)
+    }
+
+    /// A new chain with `settings`, reusing the impulse response if it's the same file.
+    pub fn rebuild(&self, settings: &DspSettings, sample_rate: u32) -> Self {
+        Self::build(settings, sample_rate, self.ir.clone())
+    }
+
+    /// The impulse response loaded for the reverb, to pass to [`DspChain::build`].
+    pub fn impulse_response(&self) -> Option<Arc<ImpulseResponse>> {
+        self.ir.clone()
+    }
+
+    /// A new chain with `settings`. `ir` is used if it's the impulse response file the
+    /// settings ask for. Loading one and preparing the convolution takes a while, so the
+    /// live bus builds chains away from the audio thread.
+    pub fn build(
+        settings: &DspSettings,
+        sample_rate: u32,
+        ir: Option<Arc<ImpulseResponse>>,
+    ) -> Self {
+        let filters = eq_filters(settings)
+            .iter()
+            .map(|band| Biquad::new(band, sample_rate))
+            .collect();
+        let reverb = &settings.reverb;
+        let ir = reverb
+            .ir_path()
+            .filter(|_| reverb.enabled)
+            .and_then(|path| match ir {
+                Some(ir) if ir.path == path => Some(ir),
+                _ => ImpulseResponse::load(&path)
+                    .map(Arc::new)
+                    .map_err(|e| log::error!("{e:#}, using the built-in room"))
+                    .ok(),
+            });
+        let compressor = &settings.compressor;
+        Self {
+            settings: settings.clone(),
+            sample_rate,
+            filters,
+            reverb: reverb
+                .enabled
+                .then(|| Reverb::new(reverb, ir.as_ref(), sample_rate)),
+            ir,
+            compressor: compressor
+                .enabled
+                .then(|| Compressor::new(compressor, sample_rate)),
+            limiter: settings
+                .limiter
+                .enabled
+                .then(|| Limiter::new(&settings.limiter, sample_rate)),
+        }
+    }
+
+    /// Switch to `settings` without resetting the filters and the reverb, if only their
+    /// parameters changed. Returns `false` if the chain has to be built again, e.g. when
+    /// the reverb's impulse response or the number of EQ bands changed.
+    pub fn update(&mut self, settings: &DspSettings) -> bool {
+        let (old, new) = (&self.settings.reverb, &settings.reverb);
+        let same_reverb = old.enabled == new.enabled && (!new.enabled || old.ir == new.ir);
+        let bands = eq_filters(settings);
+        if bands.len() != self.filters.len()
+            || !same_reverb
+            || self.compressor.is_some() != settings.compressor.enabled
+            || self.limiter.is_some() != settings.limiter.enabled
+        {
+            return false;
+        }
+        for (filter, band) in self.filters.iter_mut().zip(&bands) {
+            filter.set(band, self.sample_rate);
+        }
+        if let Some(reverb) = &mut self.reverb {
+            reverb.set_parameters(new);
+        }
+        if let Some(compressor) = &mut self.compressor {
+            compressor.set(&settings.compressor, self.sample_rate);
+        }
+        if let Some(limiter) = &mut self.limiter {
+            limiter.set(&settings.limiter, self.sample_rate);
+        }
+        self.settings = settings.clone();
+        true
+    }
+
+    pub fn settings(&self) -> &DspSettings {
+        &self.settings
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn process_frame(&mut self, frame: Frame) -> Frame {
+        let (mut left, mut right) = (frame.left as f64, frame.right as f64);
+        for filter in &mut self.filters {
+            left = filter.process(0, left);
+            right = filter.process(1, right);
+        }
+        if let Some(reverb) = &mut self.reverb {
+            let frame = reverb.process(Frame {
+                left: left as f32,
+                right: right as f32,
+            });
+            (left, right) = (frame.left as f64, frame.right as f64);
+        }
+        if let Some(compressor) = &mut self.compressor {
+            let gain = compressor.gain(left.abs().max(right.abs()));
+            left *= gain;
+            right *= gain;
+        }
+        if let Some(limiter) = &mut self.limiter {
+            let gain = limiter.gain(left.abs().max(right.abs()));
+            left *= gain;
+            right *= gain;
+        }
+        Frame {
+            left: left as f32,
+            right: right as f32,
+        }
+    }
+
+    pub fn process(&mut self, frames: &mut [Frame]) {
+        for frame in frames {
+            *frame = self.process_frame(*frame);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f64, amplitude: f32, len: usize) -> Vec<Frame> {
+        (0..len)
+            .map(|i| {
+                let s = (2.0 * PI * freq * i as f64 / 48000.0).sin() as f32 * amplitude;
+                Frame { left: s, right: s }
+            })
+            .collect()
+    }
+
+    fn peak(frames: &[Frame]) -> f32 {
+        frames
+            .iter()
+            .map(|f| f.left.abs().max(f.right.abs()))
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn limiter_keeps_peaks_under_ceiling() {
+        let settings = DspSettings::default();
+        let mut frames = sine(440.0, 3.0, 4800);
+        DspChain::new(&settings, 48000).process(&mut frames);
+        assert!(peak(&frames) <= db_to_gain(settings.limiter.ceiling_db) as f32 + 1e-6);
+
+        // quiet audio passes through untouched
+        let quiet = sine(440.0, 0.5, 4800);
+        let mut frames = quiet.clone();
+        DspChain::new(&settings, 48000).process(&mut frames);
+        assert_eq!(frames, quiet);
+    }
+
+    #[test]
+    fn eq_profiles() {
+        let gain_at = |profile, freq| {
+            let settings = DspSettings {
+                eq: EqSettings {
+                    profile,
+                    ..Default::default()
+                },
+                limiter: LimiterSettings {
+                    enabled: false,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let mut frames = sine(freq, 0.1, 9600);
+            DspChain::new(&settings, 48000).process(&mut frames);
+            peak(&frames[4800..]) / 0.1
+        };
+        assert!(gain_at(SoundProfile::Bass, 60.0) > 1.4);
+        assert!(gain_at(SoundProfile::Bass, 12000.0) < 0.9);
+        assert!(gain_at(SoundProfile::Treble, 12000.0) > 1.4);
+        assert!((gain_at(SoundProfile::Balanced, 1000.0) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn compressor_reduces_loud_parts() {
+        let settings = DspSettings {
+            compressor: CompressorSettings {
+                enabled: true,
+                ..Default::default()
+            },
+            limiter: LimiterSettings {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut frames = sine(440.0, 1.0, 9600);
+        DspChain::new(&settings, 48000).process(&mut frames);
+        // 18 dB over the threshold at 4:1 is 13.5 dB of gain reduction
+        let level = peak(&frames[4800..]) as f64;
+        assert!((20.0 * level.log10() + 13.5).abs() < 1.0, "{level}");
+    }
+
+    #[test]
+    fn parameters_update_in_place() {
+        let mut settings = DspSettings::default();
+        let mut chain = DspChain::new(&settings, 48000);
+        settings.limiter.ceiling_db = -6.0;
+        settings.eq.profile = SoundProfile::Bass;
+        // the EQ gets filters, which needs a new chain
+        assert!(!chain.clone().update(&settings));
+
+        settings.eq.profile = SoundProfile::Balanced;
+        assert!(chain.update(&settings));
+        assert_eq!(chain.settings(), &settings);
+        let mut frames = sine(440.0, 1.0, 4800);
+        chain.process(&mut frames);
+        assert!(peak(&frames) <= db_to_gain(-6.0) as f32 + 1e-6);
+
+        settings.compressor.enabled = true;
+        assert!(!chain.update(&settings));
+    }
+}
@@ -0,0 +1,813 @@
+use crate::{
+    clickpack::{
+        Button, ClickType, Clickpack, PanSettings, Pitch, SoundWrapper, Timings, VolumeSettings,
+    },
+    tiers::{Tier, Tiers},
+    timing::{TimeUnit, DEFAULT_TPS},
+    utils,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct IgnoredClickTypes {
+    pub hardclicks: bool,
+    pub hardreleases: bool,
+    pub softclicks: bool,
+    pub softreleases: bool,
+    pub clicks: bool,
+    pub releases: bool,
+    pub microclicks: bool,
+    pub microreleases: bool,
+    /// Ignored folders of custom tiers.
+    #[serde(default)]
+    pub custom: BTreeSet<String>,
+}
+
+impl IgnoredClickTypes {
+    /// Whether sounds from the category `folder` (e.g. "softclicks") are ignored.
+    pub fn is_ignored(&self, folder: &str) -> bool {
+        match folder {
+            "hardclicks" => self.hardclicks,
+            "hardreleases" => self.hardreleases,
+            "softclicks" => self.softclicks,
+            "softreleases" => self.softreleases,
+            "clicks" => self.clicks,
+            "releases" => self.releases,
+            "microclicks" => self.microclicks,
+            "microreleases" => self.microreleases,
+            _ => self.custom.contains(folder),
+        }
+    }
+
+    pub fn set_ignored(&mut self, folder: &str, ignored: bool) {
+        let flag = match folder {
+            "hardclicks" => &mut self.hardclicks,
+            "hardreleases" => &mut self.hardreleases,
+            "softclicks" => &mut self.softclicks,
+            "softreleases" => &mut self.softreleases,
+            "clicks" => &mut self.clicks,
+            "releases" => &mut self.releases,
+            "microclicks" => &mut self.microclicks,
+            "microreleases" => &mut self.microreleases,
+            _ => {
+                if ignored {
+                    self.custom.insert(folder.to_string());
+                } else {
+                    self.custom.remove(folder);
+                }
+                return;
+            }
+        };
+        *flag = ignored;
+    }
+
+    #[inline]
+    pub fn any_ignored(&self) -> bool {
+        self.hardclicks
+            || self.hardreleases
+            || self.softclicks
+            || self.softreleases
+            || self.clicks
+            || self.releases
+            || self.microclicks
+            || self.microreleases
+            || !self.custom.is_empty()
+    }
+}
+
+/// Thresholds used instead of [`ClickSettings::timings`] for one button and player.
+/// Only the default tiers have these thresholds, custom tiers always use their own.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct TimingOverrides {
+    pub jump: [Option<Timings>; 2],  // 2 players
+    pub left: [Option<Timings>; 2],  // 2 players
+    pub right: [Option<Timings>; 2], // 2 players
+}
+
+impl TimingOverrides {
+    pub fn get(&self, button: Button, player2: bool) -> Option<&Timings> {
+        let timings = match button {
+            Button::Jump => &self.jump,
+            Button::Left => &self.left,
+            Button::Right => &self.right,
+        };
+        timings[player2 as usize].as_ref()
+    }
+
+    pub fn get_mut(&mut self, button: Button, player2: bool) -> &mut Option<Timings> {
+        match button {
+            Button::Jump => &mut self.jump[player2 as usize],
+            Button::Left => &mut self.left[player2 as usize],
+            Button::Right => &mut self.right[player2 as usize],
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct ClickTime {
+    pub time: f64,
+    pub typ: ClickType,
+}
+
+#[derive(Default)]
+pub struct ClickTimes {
+    pub jump: [ClickTime; 2],  // 2 players
+    pub left: [ClickTime; 2],  // 2 players
+    pub right: [ClickTime; 2], // 2 players
+}
+
+impl ClickTimes {
+    #[inline]
+    fn set_time(&mut self, button: Button, player2: bool, t: ClickTime, decouple: bool) {
+        match button {
+            Button::Jump => self.jump[player2 as usize] = t,
+            Button::Left => self.left[player2 as usize] = t,
+            Button::Right => {
+                // TODO:
+                // WTF:  `(if decouple { self.right } else { self.left })[player2 as usize] = time``
+                //       causes a Rust miscompilation if `decouple` is false???
+                if decouple {
+                    self.right[player2 as usize] = t;
+                } else {
+                    self.left[player2 as usize] = t;
+                }
+            }
+        }
+    }
+
+    #[inline]
+    const fn get_prev_time(&self, button: Button, player2: bool, decouple: bool) -> ClickTime {
+        match button {
+            Button::Jump => self.jump[player2 as usize],
+            Button::Left => self.left[player2 as usize],
+            Button::Right => {
+                if decouple {
+                    self.right[player2 as usize]
+                } else {
+                    self.left[player2 as usize]
+                }
+            }
+        }
+    }
+}
+
+/// The part of the config that decides which click is played and how it sounds.
+///
+/// Field names match `Config`, so this can be read straight from `.dcd/config.json`.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ClickSettings {
+    pub pitch_enabled: bool,
+    pub pitch: Pitch,
+    pub timings: Timings,
+    /// Per button and player replacements for `timings`.
+    pub timing_overrides: TimingOverrides,
+    /// Custom click categories. If unset, the hardclicks to microclicks preset with
+    /// thresholds from `timings` is used.
+    pub tiers: Option<Vec<Tier>>,
+    pub volume_settings: VolumeSettings,
+    pub pan: PanSettings,
+    pub click_speedhack: f64,
+    pub force_playing_platformer: bool,
+    pub decouple_platformer: bool,
+    pub ignored_click_types: IgnoredClickTypes,
+    pub advanced_sound_filtering: bool,
+    /// Seed for click selection, pitch and volume variation. Random if unset.
+    pub seed: Option<u64>,
+    /// Don't play any of the last N sounds again for the same button, player and click
+    /// type, if the folder has enough sounds.
+    pub avoid_last: usize,
+    /// Cut the silence before each click's onset, so it lines up with the input.
+    pub trim_silence: bool,
+    /// Play the clickpack's layers (e.g. `clicks_layer`) on top of clicks.
+    pub play_layers: bool,
+    /// Unit of `timings`, `timing_overrides` and the tier thresholds.
+    pub timing_unit: TimeUnit,
+    /// Physics ticks per second of the game, for timings in frames.
+    pub tps: f64,
+}
+
+impl Default for ClickSettings {
+    fn default() -> Self {
+        Self {
+            pitch_enabled: true,
+            pitch: Pitch::default(),
+            timings: Timings::default(),
+            timing_overrides: TimingOverrides::default(),
+            tiers: None,
+            volume_settings: VolumeSettings::default(),
+            pan: PanSettings::default(),
+            click_speedhack: 1.0,
+            force_playing_platformer: false,
+            decouple_platformer: false,
+            ignored_click_types: IgnoredClickTypes::default(),
+            advanced_sound_filtering: false,
+            seed: None,
+            avoid_last: 0,
+            trim_silence: false,
+            play_layers: true,
+            timing_unit: TimeUnit::default(),
+            tps: DEFAULT_TPS,
+        }
+    }
+}
+
+impl ClickSettings {
+    /// Frame to start playing `sound` at, past the silence before its onset if
+    /// [`ClickSettings::trim_silence`] is on.
+    pub fn start_frame(&self, sound: &SoundWrapper) -> usize {
+        if self.trim_silence {
+            sound.onset
+        } else {
+            0
+        }
+    }
+
+    /// The custom tiers, or the default preset.
+    pub fn tiers(&self) -> Tiers {
+        match &self.tiers {
+            Some(tiers) => Tiers::new(tiers.clone()),
+            None => Tiers::preset(&self.timings),
+        }
+    }
+
+    /// `secs` in the unit of the timings, see [`Self::timing_unit`].
+    pub fn in_timing_unit(&self, secs: f64) -> f64 {
+        self.timing_unit.secs_to_unit(secs, self.tps)
+    }
+
+    /// Thresholds for `button` of one player, the override if there is one.
+    pub fn timings_for(&self, button: Button, player2: bool) -> &Timings {
+        self.timing_overrides
+            .get(button, player2)
+            .unwrap_or(&self.timings)
+    }
+
+    /// Tiers for `button` of one player, see [`Self::tiers`] and [`Self::timings_for`].
+    pub fn tiers_for(&self, button: Button, player2: bool) -> Tiers {
+        match &self.tiers {
+            Some(tiers) => Tiers::new(tiers.clone()),
+            None => Tiers::preset(self.timings_for(button, player2)),
+        }
+    }
+
+    /// Check for values that load fine but make the engine misbehave.
+    ///
+    /// Returns a description of every problem found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+        let mut check = |ok: bool, msg: String| {
+            if !ok {
+                problems.push(msg);
+            }
+        };
+
+        let Pitch { from, to } = self.pitch;
+        check(
+            from > 0.0 && from <= to,
+            format!("pitch range {from}..{to} must be positive and not reversed"),
+        );
+        let mut all_timings = vec![("timings".to_string(), &self.timings)];
+        for button in [Button::Jump, Button::Left, Button::Right] {
+            for player2 in [false, true] {
+                if let Some(timings) = self.timing_overrides.get(button, player2) {
+                    let name = format!("{button:?} timings of player {}", player2 as u8 + 1);
+                    all_timings.push((name, timings));
+                }
+            }
+        }
+        for (name, timings) in all_timings {
+            let Timings {
+                hard,
+                regular,
+                soft,
+            } = *timings;
+            check(
+                hard >= regular && regular >= soft && soft >= 0.0,
+                format!(
+                    "{name} must satisfy hard ({hard}) >= regular ({regular}) >= soft ({soft}) >= 0"
+                ),
+            );
+        }
+        check(
+            self.tps.is_finite() && self.tps > 0.0,
+            format!("tps ({}) must be positive", self.tps),
+        );
+        check(
+            self.click_speedhack > 0.0,
+            format!(
+                "click_speedhack ({}) must be positive",
+                self.click_speedhack
+            ),
+        );
+
+        let vol = &self.volume_settings;
+        for (name, value) in [
+            ("spam_time", vol.spam_time),
+            ("spam_vol_offset_factor", vol.spam_vol_offset_factor),
+            ("max_spam_vol_offset", vol.max_spam_vol_offset),
+            ("global_volume", vol.global_volume),
+            ("volume_var", vol.volume_var),
+            ("platformer_volume_factor", vol.platformer_volume_factor),
+        ] {
+            check(
+                value.is_finite() && value >= 0.0,
+                format!("volume_settings.{name} ({value}) must be a non-negative number"),
+            );
+        }
+        let pan = &self.pan;
+        for (name, value) in [
+            ("player1", pan.player1),
+            ("player2", pan.player2),
+            ("left", pan.left),
+            ("right", pan.right),
+        ] {
+            check(
+                (-1.0..=1.0).contains(&value),
+                format!("pan.{name} ({value}) must be between -1 and 1"),
+            );
+        }
+        check(
+            (0.0..=1.0).contains(&pan.jitter),
+            format!("pan.jitter ({}) must be between 0 and 1", pan.jitter),
+        );
+        if let Some(tiers) = &self.tiers {
+            check(!tiers.is_empty(), "tiers must not be empty".to_string());
+            problems.extend(Tiers::validate(tiers));
+        }
+        let ignored = &self.ignored_click_types;
+        if self
+            .tiers()
+            .iter()
+            .all(|tier| ignored.is_ignored(&tier.clicks))
+        {
+            problems.push("all click types are ignored, nothing will play on push".to_string());
+        }
+        problems
+    }
+}
+
+/// A click chosen by [`ClickEngine::on_action`], not yet played.
+pub struct Click {
+    pub sound: SoundWrapper,
+    /// Frame of `sound` to start playing at: its onset if silence is trimmed, 0 otherwise.
+    pub start: usize,
+    pub typ: ClickType,
+    /// Time since the previous action on the same button, in seconds.
+    pub dt: f64,
+    pub pitch: f64,
+    pub volume: f64,
+    /// Stereo position from -1 (left) to 1 (right), see [`SoundWrapper::panned`].
+    pub pan: f64,
+    /// Sounds to play on top of this one, with the same pitch and pan.
+    pub layers: Vec<LayerClick>,
+}
+
+/// A sound from a clickpack layer, see [`crate::clickpack::Layer`].
+pub struct LayerClick {
+    pub sound: SoundWrapper,
+    /// Frame of `sound` to start playing at, see [`Click::start`].
+    pub start: usize,
+    /// Volume relative to the click's.
+    pub volume: f64,
+    /// Seconds after the click.
+    pub delay: f64,
+}
+
+/// Tiers of one button and player, with the settings they were built from.
+struct ResolvedTiers {
+    custom: Option<Vec<Tier>>,
+    timings: Timings,
+    tiers: Tiers,
+}
+
+/// Turns button actions into clicks. Used by both the live bot and the offline renderer.
+pub struct ClickEngine {
+    pub prev_times: ClickTimes,
+    pub prev_pitch: f64,
+    pub prev_volume: f64,
+    pub prev_spam_offset: f64,
+    /// Source of all random choices, so seeded runs are reproducible.
+    pub rng: fastrand::Rng,
+    /// Paths of the last played sounds for each button, player and click type, newest last.
+    pub recent_clicks: HashMap<(Button, bool, ClickType), Vec<PathBuf>>,
+    /// [`ClickSettings::tiers_for`] of each button and player, built again only when the
+    /// settings change.
+    tiers: HashMap<(Button, bool), ResolvedTiers>,
+}
+
+impl Default for ClickEngine {
+    fn default() -> Self {
+        Self {
+            prev_times: ClickTimes::default(),
+            prev_pitch: f64::NAN,
+            prev_volume: f64::NAN,
+            prev_spam_offset: f64::NAN,
+            rng: fastrand::Rng::new(),
+            recent_clicks: HashMap::new(),
+            tiers: HashMap::new(),
+        }
+    }
+}
+
+impl ClickEngine {
+    pub fn new(seed: Option<u64>) -> Self {
+        let mut engine = Self::default();
+        engine.set_seed(seed);
+        engine
+    }
+
+    /// Restart the random sequence from `seed`, or from a random seed if it's `None`.
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.rng = seed.map_or_else(fastrand::Rng::new, fastrand::Rng::with_seed);
+    }
+
+    /// [`ClickSettings::tiers_for`], reusing the tiers of the previous action if the
+    /// settings they depend on didn't change.
+    fn tiers_for(&mut self, settings: &ClickSettings, button: Button, player2: bool) -> &Tiers {
+        let timings = settings.timings_for(button, player2);
+        let key = (button, player2);
+        let fresh = self.tiers.get(&key).is_some_and(|resolved| {
+            resolved.custom == settings.tiers
+                && (settings.tiers.is_some() || resolved.timings == *timings)
+        });
+        if !fresh {
+            let resolved = ResolvedTiers {
+                custom: settings.tiers.clone(),
+                timings: timings.clone(),
+                tiers: settings.tiers_for(button, player2),
+            };
+            self.tiers.insert(key, resolved);
+        }
+        &self.tiers[&key].tiers
+    }
+
+    /// Forget all previous actions, e.g. when a level is restarted.
+    pub fn reset(&mut self) {
+        self.prev_times = ClickTimes::default();
+        self.recent_clicks.clear();
+    }
+
+    /// Sounds to avoid for the next click, see [`ClickSettings::avoid_last`].
+    pub fn recent_clicks(&self, button: Button, player2: bool, typ: ClickType) -> &[PathBuf] {
+        self.recent_clicks
+            .get(&(button, player2, typ))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Remember that `sound` was played, replacing the newest entry if `replace_last` is
+    /// set (when a front end swaps the sound picked by [`Self::on_action`]).
+    pub fn remember_click(
+        &mut self,
+        settings: &ClickSettings,
+        button: Button,
+        player2: bool,
+        typ: ClickType,
+        sound: &SoundWrapper,
+        replace_last: bool,
+    ) {
+        if settings.avoid_last == 0 {
+            return;
+        }
+        let recent = self
+            .recent_clicks
+            .entry((button, player2, typ))
+            .or_default();
+        if replace_last {
+            recent.pop();
+        }
+        recent.push(sound.path.clone());
+        if recent.len() > settings.avoid_last {
+            recent.drain(..recent.len() - settings.avoid_last);
+        }
+    }
+
+    fn random_pitch(&mut self, settings: &ClickSettings) -> f64 {
+        if settings.pitch_enabled {
+            utils::f64_range(&mut self.rng, settings.pitch.from..=settings.pitch.to)
+        } else {
+            1.0
+        }
+    }
+
+    fn click_pan(&mut self, settings: &ClickSettings, button: Button, player2: bool) -> f64 {
+        let pan = &settings.pan;
+        let mut value = if player2 { pan.player2 } else { pan.player1 };
+        value += match button {
+            Button::Jump => 0.0,
+            Button::Left => pan.left,
+            Button::Right => pan.right,
+        };
+        if pan.jitter != 0.0 {
+            value += utils::f64_range(&mut self.rng, -pan.jitter..=pan.jitter);
+        }
+        value.clamp(-1.0, 1.0)
+    }
+
+    fn click_layers(
+        &mut self,
+        clickpack: &Clickpack,
+        settings: &ClickSettings,
+        typ: ClickType,
+        player2: bool,
+        button: Button,
+        click: &SoundWrapper,
+    ) -> Vec<LayerClick> {
+        let normalize = settings.volume_settings.normalize;
+        let layers = clickpack.random_layers(&mut self.rng, typ, player2, button);
+        layers
+            .into_iter()
+            .map(|(sound, layer)| {
+                // layers are mixed relative to the click, which is normalized already
+                let volume = if normalize {
+                    layer.volume * sound.norm_gain / click.norm_gain
+                } else {
+                    layer.volume
+                };
+                LayerClick {
+                    start: settings.start_frame(&sound),
+                    sound,
+                    volume,
+                    delay: if layer.jitter > 0.0 {
+                        utils::f64_range(&mut self.rng, 0.0..=layer.jitter)
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn click_volume(
+        &mut self,
+        settings: &ClickSettings,
+        button: Button,
+        player2: bool,
+        push: bool,
+        dt: f64,
+    ) -> f64 {
+        let vol = &settings.volume_settings;
+        let mut volume = 1.0;
+
+        if vol.volume_var != 0.0 {
+            let variation = utils::f64_range(&mut self.rng, -vol.volume_var..=vol.volume_var);
+            volume += if settings.advanced_sound_filtering {
+                // scale the variation with the time between clicks
+                let regular = settings.timings_for(button, player2).regular;
+                variation * (settings.in_timing_unit(dt) / regular).clamp(0.1, 2.0)
+            } else {
+                variation
+            };
+        }
+
+        if (push || vol.change_releases_volume) && dt < vol.spam_time && vol.enabled {
+            let offset = (vol.spam_time - dt) * vol.spam_vol_offset_factor;
+            self.prev_spam_offset = offset;
+            volume -= offset.min(vol.max_spam_vol_offset);
+        } else {
+            self.prev_spam_offset = 0.0;
+        }
+
+        if button.is_platformer() {
+            volume *= vol.platformer_volume_factor;
+        }
+        volume *= vol.global_volume;
+        volume.clamp(0.0, 2.0)
+    }
+
+    /// Decide which click to play for an action at `now` seconds.
+    ///
+    /// Returns `None` if nothing should be played (repeated push, ignored click type,
+    /// platformer action without platformer sounds).
+    pub fn on_action(
+        &mut self,
+        clickpack: &mut Clickpack,
+        settings: &ClickSettings,
+        button: Button,
+        player2: bool,
+        push: bool,
+        now: f64,
+    ) -> Option<Click> {
+        if clickpack.num_sounds == 0 {
+            return None;
+        }
+        if button.is_platformer()
+            && !settings.force_playing_platformer
+            && !clickpack.has_platformer_sounds
+        {
+            return None;
+        }
+
+        let prev_time =
+            self.prev_times
+                .get_prev_time(button, player2, settings.decouple_platformer);
+        if prev_time.typ.is_click() && push {
+            return None;
+        }
+        let dt = (now - prev_time.time).abs();
+        let tiers = self.tiers_for(settings, button, player2);
+        let typ = tiers.classify(push, settings.in_timing_unit(dt));
+        if settings
+            .ignored_click_types
+            .is_ignored(tiers.folder_name(typ))
+        {
+            return None;
+        }
+
+        let recent = self.recent_clicks(button, player2, typ).to_vec();
+        // nothing to play if the clickpack has no sounds at all
+        let sound = clickpack.get_random_click(&mut self.rng, typ, player2, button, &recent)?;
+        self.remember_click(settings, button, player2, typ, &sound, false);
+        let start = settings.start_frame(&sound);
+        let pitch = self.random_pitch(settings) * settings.click_speedhack;
+        let mut volume = self.click_volume(settings, button, player2, push, dt);
+        let vol = &settings.volume_settings;
+        if vol.normalize {
+            volume *= sound.normalize_gain(vol.normalize_target);
+        }
+        let pan = self.click_pan(settings, button, player2);
+        let layers = if settings.play_layers {
+            self.click_layers(clickpack, settings, typ, player2, button, &sound)
+        } else {
+            vec![]
+        };
+
+        self.prev_times.set_time(
+            button,
+            player2,
+            ClickTime { time: now, typ },
+            settings.decouple_platformer,
+        );
+        self.prev_pitch = pitch;
+        self.prev_volume = volume;
+
+        Some(Click {
+            sound,
+            start,
+            typ,
+            dt,
+            pitch,
+            volume,
+            pan,
+            layers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_overrides() {
+        let mut settings = ClickSettings::default();
+        *settings.timing_overrides.get_mut(Button::Left, false) = Some(Timings {
+            hard: 5.0,
+            regular: 1.0,
+            soft: 0.3,
+        });
+        // 0.5 s is a regular click for jumps but a soft one for the overridden button
+        let classify = |button, player2| settings.tiers_for(button, player2).classify(true, 0.5);
+        assert_eq!(classify(Button::Jump, false), ClickType::Click(1));
+        assert_eq!(classify(Button::Left, false), ClickType::Click(2));
+        assert_eq!(classify(Button::Left, true), ClickType::Click(1));
+        assert!(settings.validate().is_empty());
+
+        settings.timing_overrides.right[1] = Some(Timings {
+            hard: 0.1,
+            regular: 1.0,
+            soft: 0.0,
+        });
+        assert_eq!(settings.validate().len(), 1);
+    }
+
+    #[test]
+    fn tiers_follow_settings() {
+        let mut engine = ClickEngine::default();
+        let mut settings = ClickSettings::default();
+        let classify = |engine: &mut ClickEngine, settings: &ClickSettings| {
+            engine
+                .tiers_for(settings, Button::Left, false)
+                .classify(true, 0.5)
+        };
+        assert_eq!(classify(&mut engine, &settings), ClickType::Click(1));
+        *settings.timing_overrides.get_mut(Button::Left, false) = Some(Timings {
+            hard: 5.0,
+            regular: 1.0,
+            soft: 0.3,
+        });
+        assert_eq!(classify(&mut engine, &settings), ClickType::Click(2));
+        settings.tiers = Some(vec![Tier {
+            name: "only".to_string(),
+            threshold: 0.0,
+            clicks: "taps".to_string(),
+            releases: "lifts".to_string(),
+            fallback: vec![],
+        }]);
+        assert_eq!(classify(&mut engine, &settings), ClickType::Click(0));
+    }
+
+    #[test]
+    fn timings_in_frames() {
+        let settings = ClickSettings {
+            timings: Timings {
+                hard: 480.0,
+                regular: 36.0,
+                soft: 6.0,
+            },
+            timing_unit: TimeUnit::Frames,
+            ..Default::default()
+        };
+        let classify = |dt: f64| {
+            settings
+                .tiers_for(Button::Jump, false)
+                .classify(true, settings.in_timing_unit(dt))
+        };
+        // 10 ticks at 240 tps
+        assert_eq!(classify(10.0 / 240.0), ClickType::Click(2));
+        assert_eq!(classify(0.2), ClickType::Click(1));
+        assert_eq!(classify(0.02), ClickType::Click(3));
+
+        // the same thresholds are shorter at a higher tick rate
+        let settings = ClickSettings {
+            tps: 480.0,
+            ..settings
+        };
+        assert_eq!(
+            settings
+                .tiers_for(Button::Jump, false)
+                .classify(true, settings.in_timing_unit(0.02)),
+            ClickType::Click(2)
+        );
+        assert!(settings.validate().is_empty());
+    }
+
+    #[test]
+    fn pan() {
+        let mut settings = ClickSettings {
+            pan: PanSettings {
+                player1: -0.5,
+                player2: 0.5,
+                left: -0.25,
+                right: 0.75,
+                jitter: 0.0,
+            },
+            ..Default::default()
+        };
+        let mut engine = ClickEngine::new(Some(0));
+        assert_eq!(engine.click_pan(&settings, Button::Jump, false), -0.5);
+        assert_eq!(engine.click_pan(&settings, Button::Left, true), 0.25);
+        assert_eq!(engine.click_pan(&settings, Button::Right, true), 1.0);
+
+        settings.pan.jitter = 0.1;
+        for _ in 0..100 {
+            let pan = engine.click_pan(&settings, Button::Jump, false);
+            assert!((-0.6..=-0.4).contains(&pan));
+        }
+        assert_eq!(utils::pan_gains(0.0), (1.0, 1.0));
+        assert_eq!(utils::pan_gains(-0.5), (1.0, 0.5));
+        for pan in [-1.0, -0.5, 0.0, 0.3, 1.0] {
+            let (left, right) = utils::pan_gains(pan);
+            let (x, volume) = utils::mixer_panning(pan);
+            let mixed = ((2.0 - 2.0 * x).sqrt() * volume, (2.0 * x).sqrt() * volume);
+            assert!((mixed.0 - left).abs() < 1e-6 && (mixed.1 - right).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn action_on_first_tick() {
+        let mut clickpack = Clickpack::default();
+        let sound = kittyaudio::Sound::from_frames(44100, &[kittyaudio::Frame::default(); 4]);
+        *clickpack.player1.sounds_mut(ClickType::Click(1)).unwrap() =
+            vec![SoundWrapper::new(std::path::Path::new("clicks/1"), sound)];
+        clickpack.num_sounds = 1;
+        let settings = ClickSettings::default();
+        let mut clock = crate::timing::TickClock::default();
+        let mut engine = ClickEngine::new(Some(0));
+
+        // a macro pressing on the level's first tick gets time 0
+        let click = engine.on_action(
+            &mut clickpack,
+            &settings,
+            Button::Jump,
+            false,
+            true,
+            clock.time(),
+        );
+        assert_eq!(click.unwrap().dt, 0.0);
+        clock.tick();
+        let release = engine.on_action(
+            &mut clickpack,
+            &settings,
+            Button::Jump,
+            false,
+            false,
+            clock.time(),
+        );
+        assert!((release.unwrap().dt - 1.0 / DEFAULT_TPS).abs() < 1e-9);
+    }
+}
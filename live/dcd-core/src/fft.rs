@@ -0,0 +1,169 @@
+//! In-place radix-2 FFT, used by the convolution reverb and the spectrum view.
+
+use std::ops::{Add, Mul};
+
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct Complex {
+    pub re: f32,
+    pub im: f32,
+}
+
+impl Complex {
+    pub const fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    pub fn norm(self) -> f32 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+}
+
+/// Transform `buf`, whose length must be a power of two. The inverse transform isn't
+/// scaled, divide by the length to get the original signal back.
+pub fn fft(buf: &mut [Complex], inverse: bool) {
+    fft_with(buf, &twiddles(buf.len()), inverse);
+}
+
+/// The factors `e^(-2πik/n)` for `k` in `0..n / 2` used by an FFT of length `n`.
+pub fn twiddles(n: usize) -> Vec<Complex> {
+    (0..n / 2)
+        .map(|k| {
+            let (sin, cos) = (-2.0 * std::f64::consts::PI * k as f64 / n as f64).sin_cos();
+            Complex::new(cos as f32, sin as f32)
+        })
+        .collect()
+}
+
+/// [`fft`] with factors from [`twiddles`] of the same length, which doesn't compute any
+/// sines or allocate, for the audio thread.
+pub fn fft_with(buf: &mut [Complex], twiddles: &[Complex], inverse: bool) {
+    let n = buf.len();
+    assert!(n.is_power_of_two(), "FFT length {n} is not a power of two");
+    assert_eq!(twiddles.len(), n / 2, "twiddles are for another FFT length");
+    if n < 2 {
+        return;
+    }
+
+    // bit-reversal permutation
+    let bits = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            buf.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let stride = n / len;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let mut w = twiddles[k * stride];
+                if inverse {
+                    w.im = -w.im;
+                }
+                let a = buf[start + k];
+                let b = buf[start + k + len / 2] * w;
+                buf[start + k] = a + b;
+                buf[start + k + len / 2] = Complex::new(a.re - b.re, a.im - b.im);
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// Level in dB (0 is a full scale sine) of `bands` log-spaced frequency bands from 20 Hz
+/// up to Nyquist, after a Hann window. The length of `samples` must be a power of two.
+pub fn band_levels(samples: &[f32], sample_rate: u32, bands: usize) -> Vec<f32> {
+    let n = samples.len();
+    let mut buf: Vec<Complex> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, &s)| {
+            let window = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / n as f32).cos();
+            Complex::new(s * window, 0.0)
+        })
+        .collect();
+    fft(&mut buf, false);
+
+    let bin_hz = sample_rate as f32 / n as f32;
+    let nyquist = sample_rate as f32 / 2.0;
+    let edge = |band: usize| 20.0 * (nyquist / 20.0).powf(band as f32 / bands as f32);
+    // a full scale sine peaks at n / 4 after the window
+    let full_scale = n as f32 / 4.0;
+    (0..bands)
+        .map(|band| {
+            let lo = (edge(band) / bin_hz).round() as usize;
+            let hi = ((edge(band + 1) / bin_hz).round() as usize).clamp(lo + 1, n / 2);
+            let peak = buf[lo.min(n / 2 - 1)..hi]
+                .iter()
+                .map(|bin| bin.norm())
+                .fold(0.0, f32::max);
+            20.0 * (peak / full_scale).max(1e-6).log10()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let signal: Vec<Complex> = (0..64)
+            .map(|i| Complex::new((i as f32 * 0.3).sin(), 0.0))
+            .collect();
+        let mut buf = signal.clone();
+        fft(&mut buf, false);
+        fft(&mut buf, true);
+        for (a, b) in signal.iter().zip(&buf) {
+            assert!((a.re - b.re / 64.0).abs() < 1e-5);
+            assert!((b.im / 64.0).abs() < 1e-5);
+        }
+
+        // a pure tone lands in a single bin
+        let mut tone: Vec<Complex> = (0..64)
+            .map(|i| {
+                let t = 2.0 * std::f32::consts::PI * 4.0 * i as f32 / 64.0;
+                Complex::new(t.cos(), 0.0)
+            })
+            .collect();
+        fft(&mut tone, false);
+        assert!((tone[4].norm() - 32.0).abs() < 1e-3);
+        assert!(tone[5].norm() < 1e-3);
+    }
+
+    #[test]
+    fn tone_band() {
+        let tone: Vec<f32> = (0..2048)
+            .map(|i| (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48000.0).sin())
+            .collect();
+        let levels = band_levels(&tone, 48000, 32);
+        let loudest = (0..32)
+            .max_by(|&a, &b| levels[a].total_cmp(&levels[b]))
+            .unwrap();
+        // 1 kHz is 55% of the way up from 20 Hz to 24 kHz on a log scale, in band 17
+        assert_eq!(loudest, 17);
+        assert!(levels[loudest].abs() < 1.5, "{}", levels[loudest]);
+        assert!(levels[5] < -40.0);
+    }
+}
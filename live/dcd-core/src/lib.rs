@@ -0,0 +1,23 @@
+//! Platform-independent clickbot core: the clickpack model, click timing classification,
+//! config types and the click engine. Front ends (the Geode mod, `dcd-cli`) handle input,
+//! playback devices and UI on top of this.
+
+pub mod clickpack;
+pub mod config;
+pub mod dsp;
+pub mod engine;
+pub mod fft;
+pub mod loudness;
+pub mod manifest;
+pub mod onset;
+mod pcm_cache;
+pub mod render;
+pub mod replay;
+pub mod report;
+pub mod reverb;
+pub mod schedule;
+pub mod source;
+pub mod tiers;
+pub mod timing;
+pub mod utils;
+pub mod watch;
@@ -0,0 +1,250 @@
+//! Loudness measurements used to bring clickpack folders to the same level.
+
+use crate::{clickpack::SoundWrapper, utils::Fnv1a};
+use kittyaudio::Sound;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hasher,
+    path::{Path, PathBuf},
+};
+
+/// Window for the RMS measurement. Clicks are short and padded with silence, so the
+/// loudest window says more than the RMS of the whole file.
+const WINDOW_SECS: f64 = 0.01;
+/// Sounds quieter than this (-60 dB) are left out of folder averages.
+const SILENCE_RMS: f32 = 0.001;
+/// Limit for normalization gain, so near-silent folders don't get boosted into noise.
+const MAX_GAIN: f64 = 16.0;
+const CACHE_DIRNAME: &str = "loudness";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Loudness {
+    /// Highest sample amplitude.
+    pub peak: f32,
+    /// RMS of the loudest 10 ms window.
+    pub rms: f32,
+}
+
+impl Loudness {
+    pub fn measure(sound: &Sound) -> Self {
+        let window = ((sound.sample_rate() as f64 * WINDOW_SECS) as usize).max(1);
+        let mut peak = 0.0f32;
+        let mut max_power = 0.0f64;
+        for chunk in sound.frames.chunks(window) {
+            let mut power = 0.0;
+            for frame in chunk {
+                peak = peak.max(frame.left.abs()).max(frame.right.abs());
+                let mono = (frame.left as f64 + frame.right as f64) / 2.0;
+                power += mono * mono;
+            }
+            max_power = max_power.max(power / chunk.len() as f64);
+        }
+        Self {
+            peak,
+            rms: max_power.sqrt() as f32,
+        }
+    }
+}
+
+pub fn db_to_gain(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+/// Gain that brings `sounds` to 0 dB RMS on average, or `None` if they're all silent.
+pub(crate) fn folder_gain(sounds: &[SoundWrapper]) -> Option<f64> {
+    let (sum, count) = sounds
+        .iter()
+        .filter_map(|s| s.loudness)
+        .filter(|l| l.rms > SILENCE_RMS)
+        .fold((0.0, 0), |(sum, count), l| {
+            (sum + (l.rms as f64).powi(2), count + 1)
+        });
+    if count == 0 {
+        return None;
+    }
+    let rms = (sum / count as f64).sqrt();
+    Some((1.0 / rms).min(MAX_GAIN))
+}
+
+/// Cheap identity of a decoded sound: its length, sample rate and a few hundred samples.
+fn fingerprint(sound: &Sound) -> u64 {
+    let mut hasher = Fnv1a::default();
+    let frames = &sound.frames;
+    hasher.write_u64(frames.len() as u64);
+    hasher.write_u32(sound.sample_rate());
+    let step = (frames.len() / 256).max(1);
+    for frame in frames.iter().step_by(step) {
+        hasher.write_u32(frame.left.to_bits());
+        hasher.write_u32(frame.right.to_bits());
+    }
+    hasher.finish()
+}
+
+/// Measurements from previous loads of a clickpack, in
+/// `<cache dir>/loudness/<hash of the clickpack path>.json`. Only the sounds the clickpack
+/// still has are saved, so edited samples don't pile up.
+#[derive(Default)]
+pub(crate) struct LoudnessCache {
+    path: Option<PathBuf>,
+    entries: HashMap<u64, Loudness>,
+    /// Sounds of the loaded clickpack.
+    used: HashSet<u64>,
+    changed: bool,
+}
+
+impl LoudnessCache {
+    /// Read the cache of `clickpack` from `dir`, or start an empty one that isn't saved if
+    /// `dir` is `None`.
+    pub fn load(dir: Option<&Path>, clickpack: &Path) -> Self {
+        let Some(dir) = dir else {
+            return Self::default();
+        };
+        let mut hasher = Fnv1a::default();
+        hasher.write(clickpack.as_os_str().as_encoded_bytes());
+        let path = dir
+            .join(CACHE_DIRNAME)
+            .join(format!("{:016x}.json", hasher.finish()));
+        let entries = std::fs::read(&path)
+            .ok()
+            .and_then(|data| {
+                serde_json::from_slice(&data)
+                    .map_err(|e| log::warn!("ignoring broken loudness cache {path:?}: {e}"))
+                    .ok()
+            })
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            entries,
+            used: HashSet::new(),
+            changed: false,
+        }
+    }
+
+    pub fn measure(&mut self, sound: &Sound) -> Loudness {
+        let key = fingerprint(sound);
+        self.used.insert(key);
+        if let Some(loudness) = self.entries.get(&key) {
+            return *loudness;
+        }
+        let loudness = Loudness::measure(sound);
+        self.entries.insert(key, loudness);
+        self.changed = true;
+        loudness
+    }
+
+    /// Keep the measurement of `sound`, which was measured before.
+    pub fn keep(&mut self, sound: &Sound) {
+        self.used.insert(fingerprint(sound));
+    }
+
+    /// Write the measurements of the sounds passed to [`LoudnessCache::measure`] and
+    /// [`LoudnessCache::keep`], dropping the rest.
+    pub fn save(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let len = self.entries.len();
+        self.entries.retain(|key, _| self.used.contains(key));
+        if !self.changed && self.entries.len() == len {
+            return;
+        }
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir)
+                .map_err(|e| log::error!("failed to create {dir:?}: {e}"));
+        }
+        match serde_json::to_vec(&self.entries) {
+            Ok(data) => {
+                let _ = std::fs::write(path, data)
+                    .map_err(|e| log::error!("failed to write {path:?}: {e}"));
+            }
+            Err(e) => log::error!("failed to serialize loudness cache: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittyaudio::Frame;
+
+    /// 1 kHz sine at `amplitude`, 10 periods per measurement window.
+    fn sine(amplitude: f32) -> Sound {
+        let frames: Vec<Frame> = (0..44100)
+            .map(|i| {
+                let x = amplitude * (i as f32 * 1000.0 / 44100.0 * std::f32::consts::TAU).sin();
+                Frame { left: x, right: x }
+            })
+            .collect();
+        Sound::from_frames(44100, &frames)
+    }
+
+    #[test]
+    fn measure_sine() {
+        for amplitude in [1.0, 0.5, 0.1] {
+            let loudness = Loudness::measure(&sine(amplitude));
+            assert!((loudness.peak - amplitude).abs() < 1e-3);
+            let rms = amplitude / std::f32::consts::SQRT_2;
+            assert!((loudness.rms - rms).abs() < 1e-3 * amplitude);
+        }
+        // half the amplitude is 6 dB quieter
+        let full = Loudness::measure(&sine(1.0)).rms as f64;
+        let half = Loudness::measure(&sine(0.5)).rms as f64;
+        assert!((20.0 * (half / full).log10() + 6.02).abs() < 0.01);
+        assert_eq!(Loudness::measure(&sine(0.0)).rms, 0.0);
+    }
+
+    #[test]
+    fn gains() {
+        assert_eq!(db_to_gain(0.0), 1.0);
+        assert!((db_to_gain(20.0) - 10.0).abs() < 1e-12);
+        assert!((db_to_gain(-6.0206) - 0.5).abs() < 1e-4);
+
+        let folder = |amplitudes: &[f32]| -> Vec<SoundWrapper> {
+            amplitudes
+                .iter()
+                .map(|&a| {
+                    let mut sound = SoundWrapper::new(Path::new("a.wav"), sine(a));
+                    sound.loudness = Some(Loudness::measure(&sound.sound));
+                    sound
+                })
+                .collect()
+        };
+        // a sine with an RMS of 0.5 needs 6 dB more
+        let gain = folder_gain(&folder(&[std::f32::consts::FRAC_1_SQRT_2])).unwrap();
+        assert!((gain - 2.0).abs() < 1e-3);
+        // silent sounds don't count, and can't be boosted
+        let gain = folder_gain(&folder(&[std::f32::consts::FRAC_1_SQRT_2, 0.0])).unwrap();
+        assert!((gain - 2.0).abs() < 1e-3);
+        assert_eq!(folder_gain(&folder(&[0.0])), None);
+        assert_eq!(folder_gain(&folder(&[0.005])), Some(MAX_GAIN));
+    }
+
+    #[test]
+    fn cache_drops_removed_sounds() {
+        let dir = std::env::temp_dir().join(format!("dcd-loudness-{}", std::process::id()));
+        let pack = Path::new("clickpacks/pack");
+        let (a, b) = (sine(0.5), sine(0.25));
+        let mut cache = LoudnessCache::load(Some(&dir), pack);
+        cache.measure(&a);
+        cache.measure(&b);
+        cache.save();
+
+        // `a` was replaced, `b` is unchanged
+        let mut cache = LoudnessCache::load(Some(&dir), pack);
+        assert_eq!(cache.entries.len(), 2);
+        cache.keep(&b);
+        cache.measure(&sine(1.0));
+        cache.save();
+        let cache = LoudnessCache::load(Some(&dir), pack);
+        assert_eq!(cache.entries.len(), 2);
+        assert!(!cache.entries.contains_key(&fingerprint(&a)));
+        assert!(cache.entries.contains_key(&fingerprint(&b)));
+
+        // other clickpacks have their own cache
+        assert!(LoudnessCache::load(Some(&dir), Path::new("other"))
+            .entries
+            .is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,251 @@
+//! Optional `clickpack.json` in the clickpack root, for packs whose folders can't be
+//! guessed from their names.
+//!
+//! ```json
+//! {
+//!   "author": "someone",
+//!   "version": "1.2",
+//!   "description": "Recorded on a Logitech G305",
+//!   "noise": "extra/pc noise.wav",
+//!   "timings": { "hard": 2.0, "regular": 0.15, "soft": 0.025 },
+//!   "pitch": { "from": 0.98, "to": 1.02 },
+//!   "sounds": [
+//!     { "path": "clicks_hard_v2", "type": "hardclicks" },
+//!     { "path": "p2/klick.wav", "type": "clicks", "player": "player2", "weight": 0.5 }
+//!   ],
+//!   "weights": { "clicks_hard_v2/best.wav": 3.0 },
+//!   "layers": { "clicks_layer": { "volume": 0.5, "jitter": 0.002 } }
+//! }
+//! ```
+//!
+//! Every field is optional. `path` is a folder or a single file relative to the clickpack
+//! root, `type` is a folder name like `softclicks` or `microrelease`, and `player` is one of
+//! `player1` (default), `player2`, `left1`, `right1`, `left2`, `right2`. If `sounds` is empty,
+//! folders are matched by name as usual, which is also done if the manifest is broken.
+//!
+//! Packs with other categories, like `ultrasoftclicks` or `taps`, can recommend a
+//! [`Tier`] list in `tiers`. Like `timings` and `pitch` it's only applied when the user
+//! asks for it, and folders of tiers that aren't configured stay unused.
+//!
+//! Folders named after a category with a `_layer` suffix, like `clicks_layer`, hold sounds
+//! that are played on top of every click of that category, e.g. a separate switch
+//! transient or plastic resonance. `layers` sets their volume relative to the click and
+//! the largest random delay in seconds. Manifest sounds become layers with `"layer": {}`.
+//!
+//! Sounds are picked with a chance proportional to their weight, which is 1 by default.
+//! It can also be set with a filename suffix like `click@2.wav`, `weight` and `weights`
+//! override that.
+
+use crate::{
+    clickpack::{Pitch, Timings},
+    source::ClickpackSource,
+    tiers::Tier,
+};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+pub const MANIFEST_FILENAME: &str = "clickpack.json";
+
+fn default_player() -> String {
+    "player1".to_string()
+}
+
+/// A folder or file listed in the manifest.
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct ManifestSound {
+    pub path: PathBuf,
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(default = "default_player")]
+    pub player: String,
+    /// Weight of every sound in `path`.
+    #[serde(default)]
+    pub weight: Option<f64>,
+    /// Play these sounds on top of the category's clicks instead of as clicks.
+    #[serde(default)]
+    pub layer: Option<LayerSettings>,
+}
+
+/// How a layer is mixed with the click it belongs to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct LayerSettings {
+    /// Volume relative to the click.
+    pub volume: f64,
+    /// Largest random delay after the click, in seconds.
+    pub jitter: f64,
+}
+
+impl Default for LayerSettings {
+    fn default() -> Self {
+        Self {
+            volume: 1.0,
+            jitter: 0.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ClickpackManifest {
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// Noise file, used instead of searching for `noise*` files.
+    pub noise: Option<PathBuf>,
+    /// Timings the pack was made for.
+    pub timings: Option<Timings>,
+    /// Pitch variation the pack was made for.
+    pub pitch: Option<Pitch>,
+    /// Click categories the pack was made for, if they aren't the default ones.
+    pub tiers: Option<Vec<Tier>>,
+    pub sounds: Vec<ManifestSound>,
+    /// Weights of single files, by their path relative to the clickpack root.
+    pub weights: BTreeMap<PathBuf, f64>,
+    /// Settings of layer folders, by their path relative to the clickpack root.
+    pub layers: BTreeMap<PathBuf, LayerSettings>,
+}
+
+impl ClickpackManifest {
+    /// Read the manifest from the clickpack root, if there is one.
+    pub(crate) fn load(src: &ClickpackSource, dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(MANIFEST_FILENAME);
+        let Some(data) = src.read(&path) else {
+            return Ok(None);
+        };
+        let manifest = serde_json::from_slice(&data)
+            .with_context(|| format!("failed to parse {MANIFEST_FILENAME}"))?;
+        log::info!("loaded manifest {path:?}");
+        Ok(Some(manifest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clickpack::{ClickType, Clickpack, LoadClickpackFor};
+
+    /// A clickpack in a temporary folder with a short click in every file of `files`.
+    fn write_pack(name: &str, files: &[&str], manifest: Option<&str>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("dcd-manifest-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        for file in files {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+            for i in 0..441 {
+                writer.write_sample(((i % 20) * 1000) as i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        if let Some(manifest) = manifest {
+            std::fs::write(dir.join(MANIFEST_FILENAME), manifest).unwrap();
+        }
+        dir
+    }
+
+    fn load(dir: &Path) -> Clickpack {
+        let mut clickpack = Clickpack::default();
+        clickpack
+            .load_from_path(dir, LoadClickpackFor::All)
+            .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        clickpack
+    }
+
+    #[test]
+    fn parse() {
+        let manifest: ClickpackManifest = serde_json::from_str(
+            r#"{
+                "author": "someone",
+                "noise": "extra/pc noise.wav",
+                "timings": { "hard": 2.0, "regular": 0.15, "soft": 0.025 },
+                "sounds": [
+                    { "path": "clicks_hard_v2", "type": "hardclicks" },
+                    { "path": "p2/klick.wav", "type": "clicks", "player": "player2", "weight": 0.5 },
+                    { "path": "clicks_layer", "type": "clicks", "layer": { "volume": 0.5 } }
+                ],
+                "weights": { "clicks_hard_v2/best.wav": 3.0 }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(manifest.author.as_deref(), Some("someone"));
+        assert_eq!(manifest.version, None);
+        assert_eq!(manifest.noise, Some(PathBuf::from("extra/pc noise.wav")));
+        assert_eq!(manifest.timings.unwrap().soft, 0.025);
+        assert_eq!(manifest.sounds.len(), 3);
+        assert_eq!(manifest.sounds[0].player, "player1");
+        assert_eq!(manifest.sounds[1].weight, Some(0.5));
+        let layer = manifest.sounds[2].layer.as_ref().unwrap();
+        assert_eq!((layer.volume, layer.jitter), (0.5, 0.0));
+        assert_eq!(manifest.weights[Path::new("clicks_hard_v2/best.wav")], 3.0);
+
+        let empty: ClickpackManifest = serde_json::from_str("{}").unwrap();
+        assert!(empty.sounds.is_empty());
+    }
+
+    #[test]
+    fn sound_mapping() {
+        let dir = write_pack(
+            "mapping",
+            &[
+                "clicks_hard_v2/1.wav",
+                "clicks_hard_v2/2.wav",
+                "p2/klick.wav",
+            ],
+            Some(
+                r#"{
+                    "sounds": [
+                        { "path": "clicks_hard_v2", "type": "hardclicks" },
+                        { "path": "p2/klick.wav", "type": "clicks", "player": "player2", "weight": 0.5 }
+                    ],
+                    "weights": { "clicks_hard_v2/2.wav": 3.0 }
+                }"#,
+            ),
+        );
+        let clickpack = load(&dir);
+        assert!(clickpack.manifest.is_some());
+        let hard = clickpack.player1.sounds(ClickType::Click(0));
+        assert_eq!(hard.len(), 2);
+        assert_eq!(hard[1].weight, 3.0);
+        let clicks = clickpack.player2.sounds(ClickType::Click(1));
+        assert_eq!(clicks.len(), 1);
+        assert_eq!(clicks[0].path, dir.join("p2/klick.wav"));
+        assert_eq!(clicks[0].weight, 0.5);
+        assert_eq!(clickpack.num_sounds, 3);
+        assert!(clickpack.report.unmatched_folders.is_empty());
+    }
+
+    #[test]
+    fn fallback_to_folder_names() {
+        let files = ["player1/clicks/1.wav", "player1/softclicks/1.wav"];
+        let check = |clickpack: &Clickpack| {
+            assert_eq!(clickpack.player1.sounds(ClickType::Click(1)).len(), 1);
+            assert_eq!(clickpack.player1.sounds(ClickType::Click(2)).len(), 1);
+        };
+
+        let clickpack = load(&write_pack("missing", &files, None));
+        check(&clickpack);
+        assert!(clickpack.manifest.is_none());
+        assert_eq!(clickpack.report.manifest_error, None);
+
+        let clickpack = load(&write_pack("broken", &files, Some("{ \"sounds\": [")));
+        check(&clickpack);
+        assert!(clickpack.report.manifest_error.is_some());
+
+        let unknown = r#"{ "sounds": [{ "path": "player1/clicks", "type": "taps" }] }"#;
+        let clickpack = load(&write_pack("unknown", &files, Some(unknown)));
+        check(&clickpack);
+        assert!(clickpack.report.manifest_error.unwrap().contains("taps"));
+    }
+}
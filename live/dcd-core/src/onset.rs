@@ -0,0 +1,56 @@
+//! Finding where the audible part of a click starts.
+
+use kittyaudio::Sound;
+
+/// The onset is the first sample reaching this fraction of the peak (-20 dB).
+const ONSET_THRESHOLD: f32 = 0.1;
+/// Below this peak (-60 dB) a sound is treated as silent and not trimmed.
+const SILENCE_PEAK: f32 = 0.001;
+/// How much to keep before the onset, so the start of the attack isn't cut.
+const PRE_ROLL_SECS: f64 = 0.001;
+
+/// Index of the frame where the click's transient starts, minus a short pre-roll.
+pub fn detect(sound: &Sound) -> usize {
+    let level = |i: usize| {
+        let f = sound.frames[i];
+        f.left.abs().max(f.right.abs())
+    };
+    let peak = (0..sound.frames.len()).map(level).fold(0.0, f32::max);
+    if peak < SILENCE_PEAK {
+        return 0;
+    }
+    let threshold = peak * ONSET_THRESHOLD;
+    let onset = (0..sound.frames.len())
+        .find(|&i| level(i) >= threshold)
+        .unwrap_or(0);
+    let pre_roll = (sound.sample_rate() as f64 * PRE_ROLL_SECS) as usize;
+    onset.saturating_sub(pre_roll)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittyaudio::Frame;
+
+    fn sound(silence: usize) -> Sound {
+        let mut frames = vec![Frame::default(); silence];
+        frames.extend((0..100).map(|i| {
+            let x = 0.8 * (1.0 - i as f32 / 100.0);
+            Frame { left: x, right: -x }
+        }));
+        Sound::from_frames(48000, &frames)
+    }
+
+    #[test]
+    fn onset_is_after_silence() {
+        // 10 ms of silence, minus the 1 ms pre-roll
+        assert_eq!(detect(&sound(480)), 480 - 48);
+        assert_eq!(detect(&sound(0)), 0);
+    }
+
+    #[test]
+    fn silent_sound_is_not_trimmed() {
+        let sound = Sound::from_frames(48000, &[Frame::default(); 1000]);
+        assert_eq!(detect(&sound), 0);
+    }
+}
@@ -0,0 +1,240 @@
+//! Decoded sounds stored as raw PCM, so unchanged clickpacks don't have to be decoded
+//! again on every launch.
+//!
+//! Every sound is a file in `<cache dir>/pcm`, named after a hash of the source path,
+//! size and modification time: the magic `DCDPCM01`, the sample rate as a little-endian
+//! `u32`, the frame count as a `u64`, then interleaved `f32` frames. Files that weren't
+//! used for the longest time are deleted once the cache grows past [`MAX_CACHE_SIZE`].
+
+use crate::utils::Fnv1a;
+use anyhow::{Context, Result};
+use kittyaudio::{Frame, Sound};
+use std::{
+    fs::File,
+    hash::Hasher,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const MAGIC: &[u8; 8] = b"DCDPCM01";
+const HEADER_LEN: usize = MAGIC.len() + 4 + 8;
+const EXTENSION: &str = "pcm";
+
+/// Size of the cache in bytes above which old files are deleted.
+pub(crate) const MAX_CACHE_SIZE: u64 = 512 * 1024 * 1024;
+
+/// What a cached sound was decoded from. If any of this changes, the file is decoded again.
+pub(crate) struct CacheKey<'a> {
+    pub path: &'a Path,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl<'a> CacheKey<'a> {
+    /// Key of a file on disk, `None` if its metadata can't be read.
+    pub fn for_file(path: &'a Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            path,
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    /// Hash of the key's bytes, which stays the same across Rust versions and platforms.
+    fn hash(&self) -> u64 {
+        let mut hasher = Fnv1a::default();
+        let path = self.path.as_os_str().as_encoded_bytes();
+        hasher.write_u64(path.len() as u64);
+        hasher.write(path);
+        hasher.write(&self.size.to_le_bytes());
+        let modified = self
+            .modified
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+        match modified {
+            Some(modified) => {
+                hasher.write_u8(1);
+                hasher.write(&modified.as_secs().to_le_bytes());
+                hasher.write(&modified.subsec_nanos().to_le_bytes());
+            }
+            None => hasher.write_u8(0),
+        }
+        hasher.finish()
+    }
+}
+
+pub(crate) struct PcmCache {
+    dir: PathBuf,
+}
+
+impl PcmCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join("pcm"),
+        }
+    }
+
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{:016x}.{EXTENSION}", key.hash()))
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<Sound> {
+        let path = self.entry_path(key);
+        let data = std::fs::read(&path).ok()?;
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return None;
+        }
+        let sample_rate = u32::from_le_bytes(data[8..12].try_into().unwrap());
+        let num_frames = u64::from_le_bytes(data[12..20].try_into().unwrap()) as usize;
+        let pcm = &data[HEADER_LEN..];
+        if pcm.len() != num_frames.checked_mul(8)? {
+            return None; // written partially
+        }
+        // the modification time is when the file was last used, for `evict`
+        let _ = File::options()
+            .append(true)
+            .open(&path)
+            .and_then(|f| f.set_modified(SystemTime::now()));
+        let sample = |b: &[u8]| f32::from_le_bytes(b.try_into().unwrap());
+        let frames: Vec<Frame> = pcm
+            .chunks_exact(8)
+            .map(|b| Frame {
+                left: sample(&b[..4]),
+                right: sample(&b[4..]),
+            })
+            .collect();
+        Some(Sound::from_frames(sample_rate, &frames))
+    }
+
+    pub fn put(&self, key: &CacheKey, sound: &Sound) -> Result<()> {
+        let mut data = Vec::with_capacity(HEADER_LEN + sound.frames.len() * 8);
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&sound.sample_rate().to_le_bytes());
+        data.extend_from_slice(&(sound.frames.len() as u64).to_le_bytes());
+        for frame in sound.frames.iter() {
+            data.extend_from_slice(&frame.left.to_le_bytes());
+            data.extend_from_slice(&frame.right.to_le_bytes());
+        }
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("failed to create {:?}", self.dir))?;
+        // write next to the entry and rename, so other processes never read half a file
+        let path = self.entry_path(key);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&tmp, data).with_context(|| format!("failed to write {tmp:?}"))?;
+        if let Err(e) = std::fs::rename(&tmp, &path) {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e).with_context(|| format!("failed to move {tmp:?} to {path:?}"));
+        }
+        Ok(())
+    }
+
+    /// Delete the least recently used files until the cache is at most `max_size` bytes.
+    pub fn evict(&self, max_size: u64) {
+        let Ok(entries) = self.dir.read_dir() else {
+            return;
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let path = entry.path();
+                if path.extension()? != EXTENSION {
+                    return None;
+                }
+                let metadata = entry.metadata().ok()?;
+                Some((metadata.modified().ok()?, metadata.len(), path))
+            })
+            .collect();
+        let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        if size <= max_size {
+            return;
+        }
+        files.sort();
+        let mut removed = 0;
+        for (_, len, path) in files {
+            if size <= max_size {
+                break;
+            }
+            match std::fs::remove_file(&path) {
+                Ok(()) => {
+                    size -= len;
+                    removed += 1;
+                }
+                Err(e) => log::warn!("failed to remove {path:?}: {e}"),
+            }
+        }
+        log::info!("removed {removed} old files from the sound cache");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let dir = std::env::temp_dir().join(format!("dcd-pcm-cache-{}", std::process::id()));
+        let cache = PcmCache::new(&dir);
+        let key = CacheKey {
+            path: Path::new("pack/clicks/1.wav"),
+            size: 1234,
+            modified: None,
+        };
+        assert!(cache.get(&key).is_none());
+
+        let frames = [
+            Frame {
+                left: 0.5,
+                right: -0.25,
+            },
+            Frame {
+                left: 1.0,
+                right: 0.0,
+            },
+        ];
+        cache.put(&key, &Sound::from_frames(44100, &frames)).unwrap();
+        let sound = cache.get(&key).unwrap();
+        assert_eq!(sound.sample_rate(), 44100);
+        assert_eq!(&sound.frames[..], &frames);
+
+        let changed = CacheKey { size: 1235, ..key };
+        assert!(cache.get(&changed).is_none());
+        // file names must not change between builds
+        assert_eq!(key.hash(), 0x54f5798840542bab);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn evict() {
+        let dir = std::env::temp_dir().join(format!("dcd-pcm-evict-{}", std::process::id()));
+        let cache = PcmCache::new(&dir);
+        let sound = Sound::from_frames(44100, &[Frame::default(); 100]);
+        let paths = ["a.wav", "b.wav", "c.wav"].map(Path::new);
+        let keys = paths.map(|path| CacheKey {
+            path,
+            size: 1,
+            modified: None,
+        });
+        let now = SystemTime::now();
+        for (i, key) in keys.iter().enumerate() {
+            cache.put(key, &sound).unwrap();
+            let age = std::time::Duration::from_secs(60 * (3 - i as u64));
+            File::options()
+                .append(true)
+                .open(cache.entry_path(key))
+                .unwrap()
+                .set_modified(now - age)
+                .unwrap();
+        }
+        // using the oldest file makes it the newest
+        assert!(cache.get(&keys[0]).is_some());
+
+        let entry_size = (HEADER_LEN + 100 * 8) as u64;
+        cache.evict(entry_size * 3);
+        assert!(keys.iter().all(|key| cache.entry_path(key).exists()));
+        cache.evict(entry_size * 2);
+        assert!(!cache.entry_path(&keys[1]).exists());
+        assert!(cache.entry_path(&keys[0]).exists());
+        assert!(cache.entry_path(&keys[2]).exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
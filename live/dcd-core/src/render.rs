@@ -0,0 +1,367 @@
+//! Offline rendering of click events to audio, using the same [`ClickEngine`] as live playback.
+
+use crate::{
+    clickpack::{Button, Clickpack},
+    dsp::{DspChain, DspSettings},
+    engine::{ClickEngine, ClickSettings},
+    utils,
+};
+use anyhow::Result;
+use kittyaudio::{Frame, Sound};
+use serde::Deserialize;
+use std::{path::Path, sync::Arc};
+
+/// A single button action, `time` seconds after the level started.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClickEvent {
+    pub time: f64,
+    pub button: Button,
+    pub player2: bool,
+    pub push: bool,
+}
+
+/// Output settings. The sound options share their names with `Config`, so they can be
+/// read from `.dcd/config.json`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub sample_rate: u32,
+    /// Seconds of audio to keep after the last sound ends.
+    pub tail: f64,
+    pub cut_sounds: bool,
+    pub cut_by_releases: bool,
+    pub play_noise: bool,
+    pub noise_volume: f64,
+    pub noise_speedhack: f64,
+    pub dsp: DspSettings,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            sample_rate: 48000,
+            tail: 0.5,
+            cut_sounds: false,
+            cut_by_releases: false,
+            play_noise: false,
+            noise_volume: 1.0,
+            noise_speedhack: 1.0,
+            dsp: DspSettings::default(),
+        }
+    }
+}
+
+/// A sound placed on the output timeline.
+struct Voice {
+    frames: Arc<[Frame]>,
+    /// Source frame played at `start`.
+    offset: usize,
+    /// Source frames advanced per output frame.
+    step: f64,
+    /// Left and right channel volume.
+    gains: (f32, f32),
+    start: usize,
+    end: usize,
+    looped: bool,
+}
+
+impl Voice {
+    fn new(
+        sound: &Sound,
+        offset: usize,
+        pitch: f64,
+        volume: f64,
+        start: usize,
+        sample_rate: u32,
+    ) -> Self {
+        let step = pitch * sound.sample_rate() as f64 / sample_rate as f64;
+        let offset = offset.min(sound.frames.len());
+        let len = if step > 0.0 {
+            ((sound.frames.len() - offset) as f64 / step).ceil() as usize
+        } else {
+            0
+        };
+        Self {
+            frames: sound.frames.clone(),
+            offset,
+            step,
+            gains: (volume as f32, volume as f32),
+            start,
+            end: start + len,
+            looped: false,
+        }
+    }
+
+    /// Linearly interpolated source frame at output position `i`.
+    fn frame_at(&self, i: usize) -> Frame {
+        let len = self.frames.len();
+        let pos = self.offset as f64 + (i - self.start) as f64 * self.step;
+        let mut idx = pos as usize;
+        if self.looped {
+            idx %= len;
+        }
+        let Some(a) = self.frames.get(idx) else {
+            return Frame {
+                left: 0.0,
+                right: 0.0,
+            };
+        };
+        let next = if self.looped {
+            (idx + 1) % len
+        } else {
+            idx + 1
+        };
+        let b = self.frames.get(next).unwrap_or(a);
+        let t = pos.fract() as f32;
+        Frame {
+            left: (a.left + (b.left - a.left) * t) * self.gains.0,
+            right: (a.right + (b.right - a.right) * t) * self.gains.1,
+        }
+    }
+}
+
+/// Render `events` with `clickpack` into stereo frames at `options.sample_rate`.
+///
+/// Events do not have to be sorted. Actions go through the same [`ClickEngine`]
+/// that `Bot::on_action` uses for real input. With `settings.seed` set, the output
+/// is the same on every call.
+pub fn render(
+    clickpack: &mut Clickpack,
+    settings: &ClickSettings,
+    options: &RenderOptions,
+    events: &[ClickEvent],
+) -> Result<Vec<Frame>> {
+    if clickpack.num_sounds == 0 {
+        anyhow::bail!("clickpack has no sounds");
+    }
+    if settings.volume_settings.normalize {
+        clickpack.measure_loudness();
+    }
+    let sample_rate = options.sample_rate;
+    let mut events = events.to_vec();
+    events.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let mut engine = ClickEngine::new(settings.seed);
+    let mut voices: Vec<Voice> = vec![];
+    for event in &events {
+        let Some(click) = engine.on_action(
+            clickpack,
+            settings,
+            event.button,
+            event.player2,
+            event.push,
+            event.time,
+        ) else {
+            continue;
+        };
+        let start = (event.time.max(0.0) * sample_rate as f64).round() as usize;
+        if options.cut_sounds && (!click.typ.is_release() || options.cut_by_releases) {
+            for voice in &mut voices {
+                voice.end = voice.end.min(start.max(voice.start));
+            }
+        }
+        let (left, right) = utils::pan_gains(click.pan);
+        let layers = click.layers.iter().map(|layer| {
+            let delay = (layer.delay * sample_rate as f64).round() as usize;
+            let volume = click.volume * layer.volume;
+            (&layer.sound, layer.start, volume, start + delay)
+        });
+        let sounds = std::iter::once((&click.sound, click.start, click.volume, start));
+        for (sound, offset, volume, start) in sounds.chain(layers) {
+            let mut voice = Voice::new(sound, offset, click.pitch, volume, start, sample_rate);
+            voice.gains.0 *= left;
+            voice.gains.1 *= right;
+            voices.push(voice);
+        }
+    }
+
+    let tail = (options.tail.max(0.0) * sample_rate as f64) as usize;
+    let len = voices.iter().map(|v| v.end).max().unwrap_or(0) + tail;
+    log::info!(
+        "rendering {} clicks from {} events into {len} frames at {sample_rate} Hz",
+        voices.len(),
+        events.len()
+    );
+
+    if options.play_noise {
+        if let Some(noise) = &clickpack.noise {
+            if !noise.frames.is_empty() {
+                let mut voice = Voice::new(
+                    noise,
+                    0,
+                    options.noise_speedhack,
+                    options.noise_volume,
+                    0,
+                    sample_rate,
+                );
+                voice.looped = true;
+                voice.end = len;
+                voices.push(voice);
+            }
+        }
+    }
+
+    let mut out = vec![
+        Frame {
+            left: 0.0,
+            right: 0.0
+        };
+        len
+    ];
+    for voice in &voices {
+        for (i, frame) in out.iter_mut().enumerate().take(voice.end).skip(voice.start) {
+            let f = voice.frame_at(i);
+            frame.left += f.left;
+            frame.right += f.right;
+        }
+    }
+    if options.dsp.is_active() {
+        DspChain::new(&options.dsp, sample_rate).process(&mut out);
+    }
+    Ok(out)
+}
+
+/// Write stereo frames as a 32-bit float WAV file.
+pub fn write_wav(path: &Path, sample_rate: u32, frames: &[Frame]) -> Result<()> {
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    for frame in frames {
+        writer.write_sample(frame.left)?;
+        writer.write_sample(frame.right)?;
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Render `events` straight into a WAV file at `path`.
+pub fn render_to_wav(
+    path: &Path,
+    clickpack: &mut Clickpack,
+    settings: &ClickSettings,
+    options: &RenderOptions,
+    events: &[ClickEvent],
+) -> Result<()> {
+    let frames = render(clickpack, settings, options, events)?;
+    write_wav(path, options.sample_rate, &frames)?;
+    log::info!("wrote {} frames to {path:?}", frames.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clickpack::{ClickType, SoundWrapper};
+    use std::path::PathBuf;
+
+    /// Four different clicks and releases for player 1.
+    fn clickpack() -> Clickpack {
+        let mut clickpack = Clickpack::default();
+        for (typ, folder) in [
+            (ClickType::Click(1), "clicks"),
+            (ClickType::Release(1), "releases"),
+        ] {
+            let sounds = (1..=4)
+                .map(|i| {
+                    let frames: Vec<Frame> = (0..256)
+                        .map(|n| Frame {
+                            left: ((n * i) % 32) as f32 / 32.0,
+                            right: -(((n * i) % 16) as f32) / 16.0,
+                        })
+                        .collect();
+                    let path = PathBuf::from(format!("{folder}/{i}.wav"));
+                    SoundWrapper::new(&path, Sound::from_frames(44100, &frames))
+                })
+                .collect();
+            *clickpack.player1.sounds_mut(typ).unwrap() = sounds;
+        }
+        clickpack.num_sounds = clickpack.player1.num_sounds();
+        clickpack
+    }
+
+    fn events() -> Vec<ClickEvent> {
+        (0..40)
+            .map(|i| ClickEvent {
+                time: i as f64 * 0.05,
+                button: Button::Jump,
+                player2: false,
+                push: i % 2 == 0,
+            })
+            .collect()
+    }
+
+    fn bytes(frames: &[Frame]) -> Vec<u8> {
+        frames
+            .iter()
+            .flat_map(|f| [f.left.to_le_bytes(), f.right.to_le_bytes()])
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn seeded_render_is_reproducible() {
+        let render_seed = |seed| {
+            let settings = ClickSettings {
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let frames = render(
+                &mut clickpack(),
+                &settings,
+                &RenderOptions::default(),
+                &events(),
+            )
+            .unwrap();
+            bytes(&frames)
+        };
+        let first = render_seed(1);
+        assert!(first.iter().any(|&b| b != 0));
+        assert_eq!(first, render_seed(1));
+        assert_ne!(first, render_seed(2));
+    }
+
+    #[test]
+    fn trimmed_silence() {
+        // 10 ms of silence before the click, at the output sample rate
+        let mut frames = vec![Frame::default(); 480];
+        frames.extend(
+            [Frame {
+                left: 0.5,
+                right: 0.5,
+            }; 480],
+        );
+        let mut clickpack = Clickpack::default();
+        *clickpack.player1.sounds_mut(ClickType::Click(1)).unwrap() = vec![SoundWrapper::new(
+            Path::new("clicks/1.wav"),
+            Sound::from_frames(48000, &frames),
+        )];
+        clickpack.num_sounds = 1;
+        let event = ClickEvent {
+            time: 0.5,
+            button: Button::Jump,
+            player2: false,
+            push: true,
+        };
+        let mut first_sound = |trim_silence| {
+            let settings = ClickSettings {
+                pitch_enabled: false,
+                trim_silence,
+                ..Default::default()
+            };
+            let out = render(
+                &mut clickpack,
+                &settings,
+                &RenderOptions::default(),
+                &[event],
+            );
+            out.unwrap().iter().position(|f| f.left != 0.0).unwrap()
+        };
+        assert_eq!(first_sound(false), 24000 + 480);
+        // the 1 ms pre-roll stays
+        assert_eq!(first_sound(true), 24000 + 48);
+    }
+}
@@ -0,0 +1,294 @@
+//! Readers that turn macro files into [`ClickEvent`]s.
+//!
+//! Supported formats:
+//!
+//! * **GDR** (`.gdr`, `.gdr.json`): the JSON or MessagePack replay format. Frames are
+//!   converted with the replay's `framerate`.
+//! * **Text** (`.txt`): a plain-text dump. The first line is the FPS/TPS, either as
+//!   a bare number or as `fps 240`/`tps 240`. Every other line is
+//!   `frame button push [player2]`, where `button` is 1 (jump), 2 (left) or 3 (right)
+//!   and `push`/`player2` are `0` or `1`. Empty lines and lines starting with `#` are
+//!   skipped.
+//! * **Event list** (`.json`): a JSON object with an optional `fps` and a list of events.
+//!   Each event has either a `time` in seconds or a `frame` (which needs `fps`):
+//!
+//!   ```json
+//!   {
+//!     "fps": 240,
+//!     "events": [
+//!       { "frame": 120, "button": 1, "push": true },
+//!       { "time": 0.75, "button": 1, "player2": true, "push": false }
+//!     ]
+//!   }
+//!   ```
+//!
+//!   `player2` defaults to `false`.
+
+use crate::{clickpack::Button, render::ClickEvent};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayFormat {
+    /// GDR, either JSON or MessagePack (detected from the contents).
+    Gdr,
+    Text,
+    EventList,
+}
+
+impl ReplayFormat {
+    /// Guess the format from a file name.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_lowercase();
+        if name.ends_with(".gdr") || name.ends_with(".gdr.json") {
+            Some(Self::Gdr)
+        } else if name.ends_with(".txt") {
+            Some(Self::Text)
+        } else if name.ends_with(".json") {
+            Some(Self::EventList)
+        } else {
+            None
+        }
+    }
+}
+
+/// Read a macro file, guessing its format from the file name.
+pub fn load_replay(path: &Path) -> Result<Vec<ClickEvent>> {
+    let format = ReplayFormat::from_path(path)
+        .with_context(|| format!("unknown macro format for {path:?}"))?;
+    let data = std::fs::read(path).with_context(|| format!("failed to read {path:?}"))?;
+    parse_replay(&data, format).with_context(|| format!("failed to parse {path:?}"))
+}
+
+pub fn parse_replay(data: &[u8], format: ReplayFormat) -> Result<Vec<ClickEvent>> {
+    let events = match format {
+        ReplayFormat::Gdr => parse_gdr(data)?,
+        ReplayFormat::Text => parse_text(std::str::from_utf8(data)?)?,
+        ReplayFormat::EventList => parse_event_list(std::str::from_utf8(data)?)?,
+    };
+    log::info!("parsed {} events ({format:?})", events.len());
+    Ok(events)
+}
+
+fn button(b: u8) -> Result<Button> {
+    Button::try_from_u8(b).with_context(|| format!("invalid button {b}, expected 1..=3"))
+}
+
+fn check_fps(fps: f64) -> Result<f64> {
+    anyhow::ensure!(fps.is_finite() && fps > 0.0, "invalid fps {fps}");
+    Ok(fps)
+}
+
+#[derive(Deserialize)]
+struct GdrInput {
+    frame: u64,
+    btn: u8,
+    #[serde(rename = "2p", default)]
+    player2: bool,
+    down: bool,
+}
+
+#[derive(Deserialize)]
+struct GdrReplay {
+    #[serde(alias = "fps", alias = "tps")]
+    framerate: f64,
+    inputs: Vec<GdrInput>,
+}
+
+/// Parse a GDR replay, either JSON or MessagePack.
+pub fn parse_gdr(data: &[u8]) -> Result<Vec<ClickEvent>> {
+    let is_json = data
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|&b| b == b'{');
+    let replay: GdrReplay = if is_json {
+        serde_json::from_slice(data).context("invalid GDR json")?
+    } else {
+        rmp_serde::from_slice(data).context("invalid GDR binary")?
+    };
+    let fps = check_fps(replay.framerate)?;
+    replay
+        .inputs
+        .iter()
+        .map(|input| {
+            Ok(ClickEvent {
+                time: input.frame as f64 / fps,
+                button: button(input.btn)?,
+                player2: input.player2,
+                push: input.down,
+            })
+        })
+        .collect()
+}
+
+fn parse_flag(s: &str) -> Result<bool> {
+    match s {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => anyhow::bail!("expected 0 or 1, got {s:?}"),
+    }
+}
+
+/// Parse a plain-text `frame button push [player2]` dump.
+pub fn parse_text(s: &str) -> Result<Vec<ClickEvent>> {
+    let mut lines = s
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+    let (_, header) = lines.next().context("missing fps header")?;
+    let fps = header
+        .strip_prefix("fps")
+        .or_else(|| header.strip_prefix("tps"))
+        .unwrap_or(header)
+        .trim()
+        .parse::<f64>()
+        .with_context(|| format!("invalid fps header {header:?}"))?;
+    let fps = check_fps(fps)?;
+
+    lines
+        .map(|(n, line)| parse_text_line(line, fps).with_context(|| format!("line {n}")))
+        .collect()
+}
+
+fn parse_text_line(line: &str, fps: f64) -> Result<ClickEvent> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let [frame, btn, push, rest @ ..] = &parts[..] else {
+        anyhow::bail!("expected `frame button push [player2]`, got {line:?}");
+    };
+    let player2 = match rest {
+        [] => false,
+        [p] => parse_flag(p)?,
+        _ => anyhow::bail!("expected `frame button push [player2]`, got {line:?}"),
+    };
+    Ok(ClickEvent {
+        time: frame.parse::<u64>().context("invalid frame")? as f64 / fps,
+        button: button(btn.parse().context("invalid button")?)?,
+        player2,
+        push: parse_flag(push)?,
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListEvent {
+    time: Option<f64>,
+    frame: Option<u64>,
+    button: u8,
+    #[serde(default)]
+    player2: bool,
+    push: bool,
+}
+
+#[derive(Deserialize)]
+struct EventList {
+    #[serde(alias = "tps")]
+    fps: Option<f64>,
+    events: Vec<ListEvent>,
+}
+
+/// Parse a JSON event list (see the module docs for the layout).
+pub fn parse_event_list(s: &str) -> Result<Vec<ClickEvent>> {
+    let list: EventList = serde_json::from_str(s).context("invalid event list")?;
+    let fps = list.fps.map(check_fps).transpose()?;
+    list.events
+        .iter()
+        .enumerate()
+        .map(|(i, event)| list_event(event, fps).with_context(|| format!("event {i}")))
+        .collect()
+}
+
+fn list_event(event: &ListEvent, fps: Option<f64>) -> Result<ClickEvent> {
+    let time = match (event.time, event.frame) {
+        (Some(time), None) if time.is_finite() => time,
+        (None, Some(frame)) => frame as f64 / fps.context("`frame` used without `fps`")?,
+        _ => anyhow::bail!("expected exactly one of `time` or `frame`"),
+    };
+    Ok(ClickEvent {
+        time,
+        button: button(event.button)?,
+        player2: event.player2,
+        push: event.push,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    macro_rules! fixture {
+        ($name:literal) => {
+            include_bytes!(concat!("../fixtures/replays/", $name))
+        };
+    }
+
+    fn expected() -> Vec<ClickEvent> {
+        let event = |time, button, player2, push| ClickEvent {
+            time,
+            button,
+            player2,
+            push,
+        };
+        vec![
+            event(0.5, Button::Jump, false, true),
+            event(0.625, Button::Jump, false, false),
+            event(1.0, Button::Left, true, true),
+            event(1.5, Button::Left, true, false),
+        ]
+    }
+
+    #[test]
+    fn gdr_json() {
+        let events = parse_replay(fixture!("basic.gdr.json"), ReplayFormat::Gdr).unwrap();
+        assert_eq!(events, expected());
+    }
+
+    #[test]
+    fn gdr_binary() {
+        let events = parse_replay(fixture!("basic.gdr"), ReplayFormat::Gdr).unwrap();
+        assert_eq!(events, expected());
+    }
+
+    #[test]
+    fn text() {
+        let events = parse_replay(fixture!("basic.txt"), ReplayFormat::Text).unwrap();
+        assert_eq!(events, expected());
+    }
+
+    #[test]
+    fn event_list() {
+        let events = parse_replay(fixture!("basic.json"), ReplayFormat::EventList).unwrap();
+        assert_eq!(events, expected());
+    }
+
+    #[test]
+    fn format_from_path() {
+        let format = |name: &str| ReplayFormat::from_path(Path::new(name));
+        assert_eq!(format("a.gdr"), Some(ReplayFormat::Gdr));
+        assert_eq!(format("a.GDR.json"), Some(ReplayFormat::Gdr));
+        assert_eq!(format("a.txt"), Some(ReplayFormat::Text));
+        assert_eq!(format("a.json"), Some(ReplayFormat::EventList));
+        assert_eq!(format("a.mp3"), None);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse_replay(fixture!("bad_button.txt"), ReplayFormat::Text).is_err());
+        assert!(parse_replay(fixture!("frame_without_fps.json"), ReplayFormat::EventList).is_err());
+        assert!(parse_text("").is_err());
+        assert!(parse_text("0\n1 1 1").is_err());
+        assert!(parse_text("240\n1 1").is_err());
+        assert!(parse_text("240\n1 1 2").is_err());
+        assert!(parse_text("240\n1 1 1 0 0").is_err());
+        assert!(parse_gdr(b"{\"framerate\": 240").is_err());
+        assert!(parse_gdr(&[0xc1, 0x00]).is_err());
+        assert!(parse_gdr(&fixture!("basic.gdr")[..40]).is_err());
+        assert!(parse_event_list(r#"{"events": [{"button": 1, "push": true}]}"#).is_err());
+        assert!(parse_event_list(
+            r#"{"fps": 60, "events": [{"time": 1, "frame": 60, "button": 1, "push": true}]}"#
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,449 @@
+//! Problems found while loading a clickpack, so they can be shown without reading the log.
+
+use crate::{
+    clickpack::{Button, ClickType, Clickpack, SoundWrapper, PLAYER_BUTTONS},
+    manifest::MANIFEST_FILENAME,
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+/// Peak amplitude below which a sound is considered silent (-40 dBFS).
+const SILENCE_PEAK: f32 = 0.01;
+/// Samples at or above this amplitude count as clipped.
+const CLIP_LEVEL: f32 = 0.999;
+/// How many clipped frames in a row make a sound clipped.
+const CLIP_RUN: usize = 3;
+/// Silence before the onset that's long enough to throw off sync.
+const LATE_ONSET_SECS: f64 = 0.005;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A single problem with a hint on how to fix it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub hint: Option<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{severity}: {}", self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, " ({hint})")?;
+        }
+        Ok(())
+    }
+}
+
+/// An empty category and what gets played instead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fallback {
+    pub player: &'static str,
+    /// Folder name of the empty category.
+    pub folder: String,
+    /// Player the sounds are taken from, see [`Clickpack::lookup`].
+    pub used_player: &'static str,
+    /// Folder the sounds are taken from.
+    pub used: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClickpackReport {
+    /// Why the manifest couldn't be used, folders are matched by name instead.
+    pub manifest_error: Option<String>,
+    /// Folders that didn't match any click type.
+    pub unmatched_folders: Vec<PathBuf>,
+    /// Files that couldn't be decoded, with the decoder error.
+    pub undecodable_files: Vec<(PathBuf, String)>,
+    /// The sample rate most sounds have.
+    pub sample_rate: Option<u32>,
+    /// Sounds with a different sample rate than [`Self::sample_rate`].
+    pub sample_rate_mismatches: Vec<(PathBuf, u32)>,
+    /// Whether most sounds are mono.
+    pub mostly_mono: bool,
+    /// Sounds that are stereo when most are mono, or the other way around.
+    pub channel_mismatches: Vec<PathBuf>,
+    /// Sounds that are near-silent, with their peak amplitude.
+    pub silent_files: Vec<(PathBuf, f32)>,
+    pub clipped_files: Vec<PathBuf>,
+    /// Sounds that start with silence, with its length in seconds.
+    pub late_onsets: Vec<(PathBuf, f64)>,
+    /// Empty categories of players that have sounds.
+    pub fallbacks: Vec<Fallback>,
+    /// Players without sounds and the player whose sounds they play instead.
+    pub player_fallbacks: Vec<(&'static str, &'static str)>,
+}
+
+fn is_mono(sound: &SoundWrapper) -> bool {
+    sound.frames.iter().all(|f| f.left == f.right)
+}
+
+fn peak(sound: &SoundWrapper) -> f32 {
+    sound
+        .frames
+        .iter()
+        .map(|f| f.left.abs().max(f.right.abs()))
+        .fold(0.0, f32::max)
+}
+
+fn is_clipped(sound: &SoundWrapper) -> bool {
+    let mut run = 0;
+    for f in sound.frames.iter() {
+        if f.left.abs() >= CLIP_LEVEL || f.right.abs() >= CLIP_LEVEL {
+            run += 1;
+            if run >= CLIP_RUN {
+                return true;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    false
+}
+
+impl ClickpackReport {
+    pub(crate) fn invalid_manifest(&mut self, err: &anyhow::Error) {
+        self.manifest_error = Some(format!("{err:#}"));
+    }
+
+    // the same folder can be visited more than once when loading for a single player
+    pub(crate) fn unmatched_folder(&mut self, path: &Path) {
+        if !self.unmatched_folders.iter().any(|p| p == path) {
+            self.unmatched_folders.push(path.to_path_buf());
+        }
+    }
+
+    pub(crate) fn undecodable_file(&mut self, path: &Path, err: &anyhow::Error) {
+        if !self.undecodable_files.iter().any(|(p, _)| p == path) {
+            self.undecodable_files
+                .push((path.to_path_buf(), err.to_string()));
+        }
+    }
+
+    /// Check the loaded sounds. Replaces the results of the previous check, but keeps
+    /// problems found while loading.
+    pub(crate) fn check_sounds(&mut self, clickpack: &Clickpack) {
+        let layers = clickpack
+            .players()
+            .into_iter()
+            .flat_map(|(_, clicks)| clicks.layers.iter().flatten())
+            .flat_map(|layer| &layer.sounds);
+        let sounds: Vec<&SoundWrapper> = clickpack
+            .players()
+            .into_iter()
+            .flat_map(|(_, clicks)| clicks.folders(&clickpack.tiers))
+            .flat_map(|(_, sounds)| sounds)
+            .chain(layers)
+            .chain(&clickpack.noise)
+            .collect();
+
+        let mut rates = BTreeMap::new();
+        for sound in &sounds {
+            *rates.entry(sound.sample_rate()).or_insert(0usize) += 1;
+        }
+        self.sample_rate = rates
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(rate, _)| *rate);
+        self.sample_rate_mismatches = sounds
+            .iter()
+            .filter(|s| Some(s.sample_rate()) != self.sample_rate)
+            .map(|s| (s.path.clone(), s.sample_rate()))
+            .collect();
+
+        let mono: Vec<bool> = sounds.iter().map(|s| is_mono(s)).collect();
+        self.mostly_mono = mono.iter().filter(|m| **m).count() * 2 > mono.len();
+        self.channel_mismatches = sounds
+            .iter()
+            .zip(&mono)
+            .filter(|(_, m)| **m != self.mostly_mono)
+            .map(|(s, _)| s.path.clone())
+            .collect();
+
+        self.silent_files.clear();
+        self.clipped_files.clear();
+        self.late_onsets.clear();
+        for sound in &sounds {
+            let peak = peak(sound);
+            if peak < SILENCE_PEAK {
+                self.silent_files.push((sound.path.clone(), peak));
+            } else if is_clipped(sound) {
+                self.clipped_files.push(sound.path.clone());
+            }
+            if sound.onset_secs() > LATE_ONSET_SECS {
+                self.late_onsets
+                    .push((sound.path.clone(), sound.onset_secs()));
+            }
+        }
+
+        self.fallbacks.clear();
+        self.player_fallbacks.clear();
+        let tiers = &clickpack.tiers;
+        let players = clickpack.players();
+        for (i, (player, clicks)) in players.iter().enumerate() {
+            let (player2, button) = PLAYER_BUTTONS[i];
+            if clicks.num_sounds() == 0 {
+                // platformer buttons without sounds are normal unless the pack has some
+                let platformer = button != Button::Jump;
+                if platformer && !clickpack.has_platformer_sounds {
+                    continue;
+                }
+                if let Some((used, _)) = clickpack.lookup(ClickType::Click(0), player2, button) {
+                    self.player_fallbacks.push((player, players[used].0));
+                }
+                continue;
+            }
+            for (typ, folder) in tiers.categories() {
+                let Some((used_player, used)) = clickpack.lookup(typ, player2, button) else {
+                    continue;
+                };
+                if (used_player, used) != (i, typ) {
+                    self.fallbacks.push(Fallback {
+                        player,
+                        folder: folder.to_string(),
+                        used_player: players[used_player].0,
+                        used: tiers.folder_name(used).to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        let mut push = |severity, message: String, hint: Option<&str>| {
+            diagnostics.push(Diagnostic {
+                severity,
+                message,
+                hint: hint.map(str::to_string),
+            })
+        };
+
+        if let Some(err) = &self.manifest_error {
+            push(
+                Severity::Error,
+                format!("{MANIFEST_FILENAME} can't be used: {err}"),
+                Some("fix it, folders are matched by name until then"),
+            );
+        }
+        for path in &self.unmatched_folders {
+            push(
+                Severity::Warning,
+                format!("folder {path:?} did not match any click type"),
+                Some("rename it to e.g. \"softclicks\", map it in clickpack.json or add a tier for it"),
+            );
+        }
+        for (path, err) in &self.undecodable_files {
+            push(
+                Severity::Error,
+                format!("failed to decode {path:?}: {err}"),
+                Some("convert it to .wav, .ogg, .mp3 or .flac"),
+            );
+        }
+        if let Some(rate) = self.sample_rate {
+            for (path, other) in &self.sample_rate_mismatches {
+                push(
+                    Severity::Info,
+                    format!("{path:?} is {other} Hz, most sounds are {rate} Hz"),
+                    Some("resample it so all sounds play at the same quality"),
+                );
+            }
+        }
+        let (most, other) = if self.mostly_mono {
+            ("mono", "stereo")
+        } else {
+            ("stereo", "mono")
+        };
+        for path in &self.channel_mismatches {
+            push(
+                Severity::Info,
+                format!("{path:?} is {other}, most sounds are {most}"),
+                None,
+            );
+        }
+        for (path, peak) in &self.silent_files {
+            push(
+                Severity::Warning,
+                format!(
+                    "{path:?} is nearly silent (peak {:.1} dB)",
+                    20.0 * peak.max(1e-6).log10()
+                ),
+                Some("remove it or raise its volume"),
+            );
+        }
+        for path in &self.clipped_files {
+            push(
+                Severity::Warning,
+                format!("{path:?} is clipped"),
+                Some("lower its volume before exporting"),
+            );
+        }
+        for (path, secs) in &self.late_onsets {
+            push(
+                Severity::Info,
+                format!("{path:?} starts with {:.0} ms of silence", secs * 1000.0),
+                Some("enable \"Trim leading silence\" to keep clicks in sync"),
+            );
+        }
+        for fallback in &self.fallbacks {
+            let used = if fallback.used_player == fallback.player {
+                fallback.used.clone()
+            } else {
+                format!("{} {}", fallback.used_player, fallback.used)
+            };
+            push(
+                Severity::Info,
+                format!(
+                    "{}: no {}, {used} will be played instead",
+                    fallback.player, fallback.folder
+                ),
+                None,
+            );
+        }
+        for (player, used) in &self.player_fallbacks {
+            push(
+                Severity::Info,
+                format!("{player}: no sounds, {used} sounds will be played instead"),
+                None,
+            );
+        }
+
+        diagnostics.sort_by_key(|d| std::cmp::Reverse(d.severity));
+        diagnostics
+    }
+
+    /// Number of warnings and errors.
+    pub fn num_problems(&self) -> usize {
+        self.diagnostics()
+            .iter()
+            .filter(|d| d.severity >= Severity::Warning)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kittyaudio::{Frame, Sound};
+
+    fn sounds(folder: &str) -> Vec<SoundWrapper> {
+        let frame = Frame {
+            left: 0.5,
+            right: 0.5,
+        };
+        let path = PathBuf::from(format!("{folder}/1.wav"));
+        vec![SoundWrapper::new(
+            &path,
+            Sound::from_frames(44100, &[frame; 64]),
+        )]
+    }
+
+    #[test]
+    fn fallbacks_follow_lookup() {
+        let mut clickpack = Clickpack::default();
+        for typ in [ClickType::Click(1), ClickType::Release(1)] {
+            *clickpack.player1.sounds_mut(typ).unwrap() = sounds("player1");
+        }
+        *clickpack.left1.sounds_mut(ClickType::Click(2)).unwrap() = sounds("left1");
+        clickpack.has_platformer_sounds = true;
+
+        let mut report = ClickpackReport::default();
+        report.check_sounds(&clickpack);
+        let fallback = |player, folder: &str| {
+            report
+                .fallbacks
+                .iter()
+                .find(|f| f.player == player && f.folder == folder)
+                .map(|f| (f.used_player, f.used.as_str()))
+        };
+        assert_eq!(fallback("player1", "clicks"), None);
+        assert_eq!(
+            fallback("player1", "hardclicks"),
+            Some(("player1", "clicks"))
+        );
+        assert_eq!(
+            fallback("player1", "softreleases"),
+            Some(("player1", "releases"))
+        );
+        // left1 only has softclicks, and takes everything else from them
+        assert_eq!(
+            fallback("left1", "hardreleases"),
+            Some(("left1", "softclicks"))
+        );
+        assert_eq!(
+            report.player_fallbacks,
+            [
+                ("player2", "player1"),
+                ("right1", "left1"),
+                ("left2", "left1"),
+                ("right2", "left1"),
+            ]
+        );
+        assert!(report
+            .diagnostics()
+            .iter()
+            .any(|d| d.message == "player2: no sounds, player1 sounds will be played instead"));
+
+        // without platformer sounds, the platformer buttons aren't mentioned
+        clickpack.left1 = Default::default();
+        clickpack.has_platformer_sounds = false;
+        report.check_sounds(&clickpack);
+        assert_eq!(report.player_fallbacks, [("player2", "player1")]);
+    }
+
+    #[test]
+    fn played_sounds_match_report() {
+        let mut clickpack = Clickpack::default();
+        *clickpack.player1.sounds_mut(ClickType::Click(1)).unwrap() = sounds("player1");
+        *clickpack.player2.sounds_mut(ClickType::Click(1)).unwrap() = sounds("player2");
+        *clickpack.left1.sounds_mut(ClickType::Click(2)).unwrap() = sounds("left1");
+        clickpack.has_platformer_sounds = true;
+        let mut report = ClickpackReport::default();
+        report.check_sounds(&clickpack);
+
+        let mut rng = fastrand::Rng::with_seed(1);
+        for (i, (player2, button)) in PLAYER_BUTTONS.into_iter().enumerate() {
+            let player = clickpack.players()[i].0;
+            for typ in [
+                ClickType::Click(0),
+                ClickType::Click(1),
+                ClickType::Release(2),
+            ] {
+                let folder = clickpack.tiers.folder_name(typ).to_string();
+                let used_player = report
+                    .player_fallbacks
+                    .iter()
+                    .find(|(p, _)| *p == player)
+                    .map(|(_, used)| *used);
+                let fallback = report
+                    .fallbacks
+                    .iter()
+                    .find(|f| f.player == player && f.folder == folder)
+                    .map(|f| f.used_player);
+                let expected = used_player.or(fallback).unwrap_or(player);
+                let click = clickpack
+                    .get_random_click(&mut rng, typ, player2, button, &[])
+                    .unwrap();
+                assert_eq!(
+                    click.path,
+                    Path::new(&format!("{expected}/1.wav")),
+                    "{player} {folder}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Reverb on the click bus, so dry clicks sit in the same room as the rest of a
+//! recording. Either a convolution with an impulse response WAV from [`IR_DIR`] or a
+//! built-in algorithmic room (Freeverb).
+
+use crate::fft::{fft_with, twiddles, Complex};
+use anyhow::{Context, Result};
+use kittyaudio::{Frame, Sound};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Where impulse responses are looked up.
+pub const IR_DIR: &str = ".dcd/ir";
+
+/// Longest impulse response used, anything after this is cut off. Clicks decay long before
+/// and every second costs about 190 FFT partitions at 48 kHz.
+const MAX_IR_SECS: f64 = 3.0;
+
+/// Convolution block size. The wet signal is delayed by this many frames.
+const BLOCK: usize = 256;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct ReverbSettings {
+    pub enabled: bool,
+    /// Impulse response WAV, relative to [`IR_DIR`]. The built-in room is used if unset.
+    pub ir: Option<PathBuf>,
+    /// 0 is only the dry signal, 1 only the reverb.
+    pub mix: f64,
+    /// Size of the built-in room, 0 to 1.
+    pub room_size: f64,
+    /// High frequency damping of the built-in room, 0 to 1.
+    pub damping: f64,
+}
+
+impl Default for ReverbSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ir: None,
+            mix: 0.15,
+            room_size: 0.4,
+            damping: 0.5,
+        }
+    }
+}
+
+impl ReverbSettings {
+    pub fn ir_path(&self) -> Option<PathBuf> {
+        self.ir.as_ref().map(|ir| Path::new(IR_DIR).join(ir))
+    }
+}
+
+/// Every file in [`IR_DIR`], relative to it.
+pub fn list_impulse_responses() -> Vec<PathBuf> {
+    let Ok(dir) = Path::new(IR_DIR).read_dir() else {
+        return vec![];
+    };
+    let mut names: Vec<PathBuf> = dir
+        .flatten()
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| PathBuf::from(entry.file_name()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// A decoded impulse response, normalized so that rooms of different loudness give a
+/// similar amount of reverb.
+#[derive(Debug)]
+pub struct ImpulseResponse {
+    pub path: PathBuf,
+    sample_rate: u32,
+    frames: Vec<Frame>,
+}
+
+impl ImpulseResponse {
+    pub fn load(path: &Path) -> Result<Self> {
+        let sound = Sound::from_path(path)
+            .with_context(|| format!("failed to load impulse response {path:?}"))?;
+        let max_len = (MAX_IR_SECS * sound.sample_rate() as f64) as usize;
+        let frames = &sound.frames[..sound.frames.len().min(max_len)];
+        let energy = |channel: fn(&Frame) -> f32| {
+            frames
+                .iter()
+                .map(|f| channel(f).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        };
+        let energy = energy(|f| f.left).max(energy(|f| f.right));
+        if energy == 0.0 {
+            anyhow::bail!("impulse response {path:?} is silent");
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            sample_rate: sound.sample_rate(),
+            frames: frames
+                .iter()
+                .map(|f| Frame {
+                    left: f.left / energy,
+                    right: f.right / energy,
+                })
+                .collect(),
+        })
+    }
+
+    /// One channel at `sample_rate`, linearly interpolated.
+    fn channel(&self, right: bool, sample_rate: u32) -> Vec<f32> {
+        let sample = |i: usize| {
+            self.frames
+                .get(i)
+                .map_or(0.0, |f| if right { f.right } else { f.left })
+        };
+        let step = self.sample_rate as f64 / sample_rate as f64;
+        let len = (self.frames.len() as f64 / step).ceil() as usize;
+        (0..len)
+            .map(|i| {
+                let pos = i as f64 * step;
+                let (idx, t) = (pos as usize, pos.fract() as f32);
+                sample(idx) + (sample(idx + 1) - sample(idx)) * t
+            })
+            .collect()
+    }
+}
+
+/// Uniformly partitioned overlap-save convolution of one channel.
+#[derive(Clone, Debug)]
+struct Convolver {
+    /// Spectrum of every `BLOCK` frames of the impulse response.
+    partitions: Vec<Vec<Complex>>,
+    /// Spectra of the last inputs, `head` is the newest.
+    history: Vec<Vec<Complex>>,
+    head: usize,
+    /// The previous and the current input block.
+    input: Vec<f32>,
+    output: Vec<f32>,
+    pos: usize,
+    /// Sum of the partitions' products, kept so the audio thread doesn't allocate.
+    acc: Vec<Complex>,
+    twiddles: Vec<Complex>,
+}
+
+impl Convolver {
+    fn new(ir: &[f32]) -> Self {
+        let twiddles = twiddles(2 * BLOCK);
+        let partitions: Vec<Vec<Complex>> = ir
+            .chunks(BLOCK)
+            .map(|chunk| {
+                let mut spectrum = vec![Complex::default(); 2 * BLOCK];
+                for (bin, &s) in spectrum.iter_mut().zip(chunk) {
+                    bin.re = s;
+                }
+                fft_with(&mut spectrum, &twiddles, false);
+                spectrum
+            })
+            .collect();
+        Self {
+            history: vec![vec![Complex::default(); 2 * BLOCK]; partitions.len().max(1)],
+            partitions,
+            head: 0,
+            input: vec![0.0; 2 * BLOCK],
+            output: vec![0.0; BLOCK],
+            pos: 0,
+            acc: vec![Complex::default(); 2 * BLOCK],
+            twiddles,
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        let y = self.output[self.pos];
+        self.input[BLOCK + self.pos] = x;
+        self.pos += 1;
+        if self.pos == BLOCK {
+            self.pos = 0;
+            self.run_block();
+        }
+        y
+    }
+
+    fn run_block(&mut self) {
+        let n = self.history.len();
+        self.head = (self.head + 1) % n;
+        let spectrum = &mut self.history[self.head];
+        for (bin, &s) in spectrum.iter_mut().zip(&self.input) {
+            *bin = Complex::new(s, 0.0);
+        }
+        fft_with(spectrum, &self.twiddles, false);
+
+        self.acc.fill(Complex::default());
+        for (p, partition) in self.partitions.iter().enumerate() {
+            let input = &self.history[(self.head + n - p) % n];
+            for ((a, &x), &h) in self.acc.iter_mut().zip(input).zip(partition) {
+                *a = *a + x * h;
+            }
+        }
+        fft_with(&mut self.acc, &self.twiddles, true);
+        // the first half wraps around, the second half is the output
+        let scale = 1.0 / (2 * BLOCK) as f32;
+        for (out, bin) in self.output.iter_mut().zip(&self.acc[BLOCK..]) {
+            *out = bin.re * scale;
+        }
+        self.input.copy_within(BLOCK.., 0);
+    }
+}
+
+/// Freeverb by Jezar at Dreampoint: parallel comb filters into serial allpasses.
+#[derive(Clone, Debug)]
+struct Room {
+    combs: [Vec<Comb>; 2],
+    allpasses: [Vec<Allpass>; 2],
+}
+
+#[derive(Clone, Debug)]
+struct Comb {
+    buf: Vec<f32>,
+    pos: usize,
+    filter: f32,
+    feedback: f32,
+    damp: f32,
+}
+
+#[derive(Clone, Debug)]
+struct Allpass {
+    buf: Vec<f32>,
+    pos: usize,
+}
+
+impl Room {
+    /// Delay lengths at 44.1 kHz.
+    const COMBS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+    const ALLPASSES: [usize; 4] = [556, 441, 341, 225];
+    /// Added to the right channel's delays to widen the stereo image.
+    const SPREAD: usize = 23;
+
+    /// Comb feedback and damping for `settings`.
+    fn coefs(settings: &ReverbSettings) -> (f32, f32) {
+        let feedback = settings.room_size.clamp(0.0, 1.0) as f32 * 0.28 + 0.7;
+        let damp = settings.damping.clamp(0.0, 1.0) as f32 * 0.4;
+        (feedback, damp)
+    }
+
+    fn new(settings: &ReverbSettings, sample_rate: u32) -> Self {
+        let scale = |len: usize| (len as f64 * sample_rate as f64 / 44100.0).max(1.0) as usize;
+        let (feedback, damp) = Self::coefs(settings);
+        let channel = |spread| {
+            let combs = Self::COMBS
+                .iter()
+                .map(|&len| Comb {
+                    buf: vec![0.0; scale(len + spread)],
+                    pos: 0,
+                    filter: 0.0,
+                    feedback,
+                    damp,
+                })
+                .collect();
+            let allpasses = Self::ALLPASSES
+                .iter()
+                .map(|&len| Allpass {
+                    buf: vec![0.0; scale(len + spread)],
+                    pos: 0,
+                })
+                .collect();
+            (combs, allpasses)
+        };
+        let (left_combs, left_allpasses) = channel(0);
+        let (right_combs, right_allpasses) = channel(Self::SPREAD);
+        Self {
+            combs: [left_combs, right_combs],
+            allpasses: [left_allpasses, right_allpasses],
+        }
+    }
+
+    fn process(&mut self, channel: usize, x: f32) -> f32 {
+        let input = x * 0.015;
+        let mut out = 0.0;
+        for comb in &mut self.combs[channel] {
+            let y = comb.buf[comb.pos];
+            comb.filter = y * (1.0 - comb.damp) + comb.filter * comb.damp;
+            comb.buf[comb.pos] = input + comb.filter * comb.feedback;
+            comb.pos = (comb.pos + 1) % comb.buf.len();
+            out += y;
+        }
+        for allpass in &mut self.allpasses[channel] {
+            let delayed = allpass.buf[allpass.pos];
+            allpass.buf[allpass.pos] = out + delayed * 0.5;
+            allpass.pos = (allpass.pos + 1) % allpass.buf.len();
+            out = delayed - out;
+        }
+        out * 3.0
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Wet {
+    Convolution(Box<[Convolver; 2]>),
+    Room(Room),
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Reverb {
+    mix: f32,
+    wet: Wet,
+}
+
+impl Reverb {
+    /// Convolve with `ir` if it's set, use the built-in room otherwise.
+    pub fn new(
+        settings: &ReverbSettings,
+        ir: Option<&Arc<ImpulseResponse>>,
+        sample_rate: u32,
+    ) -> Self {
+        let wet = match ir {
+            Some(ir) => Wet::Convolution(Box::new(
+                [false, true].map(|right| Convolver::new(&ir.channel(right, sample_rate))),
+            )),
+            None => Wet::Room(Room::new(settings, sample_rate)),
+        };
+        Self {
+            mix: settings.mix.clamp(0.0, 1.0) as f32,
+            wet,
+        }
+    }
+
+    /// Use the mix, room size and damping of `settings`, keeping what's still ringing.
+    pub fn set_parameters(&mut self, settings: &ReverbSettings) {
+        self.mix = settings.mix.clamp(0.0, 1.0) as f32;
+        if let Wet::Room(room) = &mut self.wet {
+            let (feedback, damp) = Room::coefs(settings);
+            for comb in room.combs.iter_mut().flatten() {
+                comb.feedback = feedback;
+                comb.damp = damp;
+            }
+        }
+    }
+
+    pub fn process(&mut self, frame: Frame) -> Frame {
+        let (left, right) = match &mut self.wet {
+            Wet::Convolution(convolvers) => {
+                let [l, r] = &mut **convolvers;
+                (l.process(frame.left), r.process(frame.right))
+            }
+            Wet::Room(room) => (room.process(0, frame.left), room.process(1, frame.right)),
+        };
+        Frame {
+            left: frame.left * (1.0 - self.mix) + left * self.mix,
+            right: frame.right * (1.0 - self.mix) + right * self.mix,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convolution_matches_direct() {
+        let ir: Vec<f32> = (0..600)
+            .map(|i| ((i * 7) % 13) as f32 / 13.0 - 0.5)
+            .collect();
+        let input: Vec<f32> = (0..2000).map(|i| (i as f32 * 0.05).sin()).collect();
+        let mut convolver = Convolver::new(&ir);
+        let output: Vec<f32> = input.iter().map(|&x| convolver.process(x)).collect();
+        for (i, &y) in output.iter().enumerate().skip(BLOCK) {
+            let n = i - BLOCK;
+            let direct: f32 = (0..=n.min(ir.len() - 1))
+                .map(|k| ir[k] * input[n - k])
+                .sum();
+            assert!((y - direct).abs() < 1e-3, "frame {i}: {y} != {direct}");
+        }
+    }
+}
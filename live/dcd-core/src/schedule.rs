@@ -0,0 +1,173 @@
+//! Placing clicks at exact output frames, so the spacing between clicks matches the
+//! spacing between inputs instead of depending on where audio buffers start.
+//!
+//! The first click is played a fixed look-ahead after the frame being rendered. Every
+//! following click is placed relative to it by its timestamp, until one would land in
+//! the past or too far ahead (the level restarted, the game lagged, or the clocks
+//! drifted apart), which starts over from the look-ahead.
+//!
+//! [`ClickTiming`] and [`TimingStats`] measure how clicks were actually played.
+
+/// Inputs further apart than this aren't compared by [`spacing_error`].
+const MAX_GAP: f64 = 1.0;
+
+#[derive(Clone, Debug, Default)]
+pub struct ClickScheduler {
+    /// Timestamp and output frame of the click everything is placed relative to.
+    anchor: Option<(f64, u64)>,
+}
+
+impl ClickScheduler {
+    /// Output frame to start a click at, for an action at `time` seconds. `now` is the
+    /// next frame to be rendered and `lookahead` is in frames.
+    pub fn frame_for(&mut self, time: f64, now: u64, sample_rate: u32, lookahead: u64) -> u64 {
+        if let Some((anchor_time, anchor_frame)) = self.anchor {
+            let offset = ((time - anchor_time) * sample_rate as f64).round();
+            if offset >= 0.0 {
+                let frame = anchor_frame + offset as u64;
+                if frame >= now && frame <= now + 2 * lookahead {
+                    return frame;
+                }
+            }
+        }
+        let frame = now + lookahead;
+        self.anchor = Some((time, frame));
+        frame
+    }
+
+    /// Start over from the look-ahead on the next click.
+    pub fn reset(&mut self) {
+        self.anchor = None;
+    }
+}
+
+/// How one click was actually played.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClickTiming {
+    /// Seconds from queueing the click until the audio callback started it.
+    pub latency: f64,
+    /// Seconds the spacing to the previous click differs from the spacing between their
+    /// inputs, see [`spacing_error`].
+    pub error: Option<f64>,
+}
+
+/// How far off the spacing between two clicks is, in seconds. `prev` and `time` are the
+/// input timestamps and `prev_frame` and `frame` the output frames the clicks started at.
+/// `None` if the inputs are out of order or too far apart to compare.
+pub fn spacing_error(
+    (prev, prev_frame): (f64, u64),
+    time: f64,
+    frame: u64,
+    sample_rate: u32,
+) -> Option<f64> {
+    let input = time - prev;
+    if !(0.0..=MAX_GAP).contains(&input) {
+        return None;
+    }
+    let output = (frame as f64 - prev_frame as f64) / sample_rate as f64;
+    Some(output - input)
+}
+
+/// Summary of recent [`ClickTiming`]s, in seconds.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TimingStats {
+    pub clicks: usize,
+    pub latency_mean: f64,
+    pub latency_max: f64,
+    /// Standard deviation of the latency.
+    pub jitter: f64,
+    /// Mean absolute spacing error.
+    pub error_mean: f64,
+    pub error_max: f64,
+    /// Share of clicks, 0 to 1, with a spacing error within the tolerance.
+    pub in_tolerance: f64,
+}
+
+impl TimingStats {
+    pub fn new(timings: &[ClickTiming], tolerance: f64) -> Self {
+        if timings.is_empty() {
+            return Self::default();
+        }
+        let n = timings.len() as f64;
+        let latency_mean = timings.iter().map(|t| t.latency).sum::<f64>() / n;
+        let variance = timings
+            .iter()
+            .map(|t| (t.latency - latency_mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let errors: Vec<f64> = timings
+            .iter()
+            .filter_map(|t| t.error)
+            .map(f64::abs)
+            .collect();
+        let (error_mean, in_tolerance) = if errors.is_empty() {
+            (0.0, 1.0)
+        } else {
+            let within = errors.iter().filter(|&&e| e <= tolerance).count();
+            (
+                errors.iter().sum::<f64>() / errors.len() as f64,
+                within as f64 / errors.len() as f64,
+            )
+        };
+        Self {
+            clicks: timings.len(),
+            latency_mean,
+            latency_max: timings.iter().map(|t| t.latency).fold(0.0, f64::max),
+            jitter: variance.sqrt(),
+            error_mean,
+            error_max: errors.iter().copied().fold(0.0, f64::max),
+            in_tolerance,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_input_spacing() {
+        let mut scheduler = ClickScheduler::default();
+        // rendering happens in 512 frame bursts, clicks 10 ms apart
+        assert_eq!(scheduler.frame_for(1.0, 0, 48000, 1000), 1000);
+        assert_eq!(scheduler.frame_for(1.01, 512, 48000, 1000), 1480);
+        assert_eq!(scheduler.frame_for(1.02, 512, 48000, 1000), 1960);
+        assert_eq!(scheduler.frame_for(1.03, 1536, 48000, 1000), 2440);
+
+        // time went backwards (restart), start over
+        assert_eq!(scheduler.frame_for(0.5, 2048, 48000, 1000), 3048);
+        // a long pause puts the next click in the past, start over too
+        assert_eq!(scheduler.frame_for(0.6, 100_000, 48000, 1000), 101_000);
+    }
+
+    #[test]
+    fn timing_stats() {
+        assert!(spacing_error((1.0, 0), 1.01, 480, 48000).unwrap().abs() < 1e-9);
+        assert!((spacing_error((1.0, 0), 1.01, 1024, 48000).unwrap() - 0.01133).abs() < 1e-4);
+        assert_eq!(spacing_error((1.0, 0), 0.5, 1024, 48000), None);
+        assert_eq!(spacing_error((1.0, 0), 3.0, 1024, 48000), None);
+
+        let timings = [
+            ClickTiming {
+                latency: 0.010,
+                error: None,
+            },
+            ClickTiming {
+                latency: 0.020,
+                error: Some(0.0005),
+            },
+            ClickTiming {
+                latency: 0.030,
+                error: Some(-0.004),
+            },
+        ];
+        let stats = TimingStats::new(&timings, 0.001);
+        assert_eq!(stats.clicks, 3);
+        assert!((stats.latency_mean - 0.020).abs() < 1e-9);
+        assert_eq!(stats.latency_max, 0.030);
+        assert!((stats.jitter - 0.00816).abs() < 1e-5);
+        assert!((stats.error_mean - 0.00225).abs() < 1e-9);
+        assert_eq!(stats.error_max, 0.004);
+        assert_eq!(stats.in_tolerance, 0.5);
+    }
+}
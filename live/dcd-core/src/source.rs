@@ -0,0 +1,307 @@
+//! Where clickpack files are read from: a plain directory or a zip archive.
+
+use crate::{
+    clickpack::{SoundWrapper, CLICKPACK_DIRNAMES},
+    manifest::MANIFEST_FILENAME,
+    pcm_cache::{CacheKey, PcmCache, MAX_CACHE_SIZE},
+    tiers::Tiers,
+};
+use anyhow::{Context, Result};
+use kittyaudio::Sound;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{Cursor, Read},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Whether `path` is a zip archive that can be loaded as a clickpack.
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Extensions of the sound files kept from archives.
+const AUDIO_EXTENSIONS: [&str; 8] = ["wav", "ogg", "mp3", "flac", "m4a", "aac", "aif", "aiff"];
+
+/// Whether an archive entry is needed to load the clickpack: a sound or the manifest.
+/// Readmes, images and the like aren't kept in memory.
+fn is_clickpack_file(name: &Path) -> bool {
+    name.file_name().is_some_and(|n| n == MANIFEST_FILENAME)
+        || name.extension().is_some_and(|ext| {
+            AUDIO_EXTENSIONS
+                .iter()
+                .any(|audio| ext.eq_ignore_ascii_case(audio))
+        })
+}
+
+/// Files of a zipped clickpack, keyed by the path they would have if the archive
+/// was a folder, e.g. `clickpacks/pack.zip/player1/clicks/1.wav`.
+pub(crate) struct ZipContents {
+    files: BTreeMap<PathBuf, Vec<u8>>,
+    dirs: BTreeSet<PathBuf>,
+    modified: Option<SystemTime>,
+}
+
+/// Whether a folder is part of the clickpack layout itself (`player1`, `softclicks`, ...),
+/// as opposed to a folder wrapping the whole pack.
+fn is_layout_folder(name: &str, tiers: &Tiers) -> bool {
+    CLICKPACK_DIRNAMES.contains(&name.to_lowercase().as_str())
+        || tiers.category_from_folder_name(name).is_some()
+}
+
+impl ZipContents {
+    fn open(path: &Path, tiers: &Tiers) -> Result<Self> {
+        let f = std::fs::File::open(path).with_context(|| format!("failed to open {path:?}"))?;
+        let modified = f.metadata().and_then(|m| m.modified()).ok();
+        let mut archive =
+            zip::ZipArchive::new(f).with_context(|| format!("{path:?} is not a zip"))?;
+
+        let mut entries = vec![];
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            if file.is_dir() {
+                continue;
+            }
+            let Some(name) = file.enclosed_name() else {
+                log::warn!("skipping unsafe path {:?} in {path:?}", file.name());
+                continue;
+            };
+            if name.starts_with("__MACOSX") || !is_clickpack_file(&name) {
+                continue;
+            }
+            let mut data = Vec::with_capacity(file.size() as usize);
+            file.read_to_end(&mut data)?;
+            entries.push((name, data));
+        }
+
+        // packs are often zipped as `pack.zip/pack/...`, load from the inner folder
+        let mut strip = PathBuf::new();
+        loop {
+            let mut top = entries.iter().map(|(name, _)| {
+                let mut components = name.strip_prefix(&strip).unwrap().components();
+                (components.next(), components.next().is_some())
+            });
+            let Some((Some(Component::Normal(first)), true)) = top.next() else {
+                break;
+            };
+            let single = top.all(|(c, nested)| nested && c == Some(Component::Normal(first)));
+            if !single || is_layout_folder(&first.to_string_lossy(), tiers) {
+                break;
+            }
+            strip.push(first);
+        }
+        if !strip.as_os_str().is_empty() {
+            log::info!("loading {path:?} from nested folder {strip:?}");
+        }
+
+        let mut contents = Self {
+            files: BTreeMap::new(),
+            dirs: BTreeSet::from([path.to_path_buf()]),
+            modified,
+        };
+        for (name, data) in entries {
+            let full = path.join(name.strip_prefix(&strip).unwrap());
+            for dir in full.ancestors().skip(1) {
+                if !contents.dirs.insert(dir.to_path_buf()) || dir == path {
+                    break;
+                }
+            }
+            contents.files.insert(full, data);
+        }
+        Ok(contents)
+    }
+}
+
+pub(crate) struct ClickpackSource {
+    /// `None` if the clickpack is a plain directory.
+    zip: Option<ZipContents>,
+    cache: Option<PcmCache>,
+}
+
+impl ClickpackSource {
+    /// Open a clickpack directory or archive. Decoded sounds are cached in `cache_dir`,
+    /// `tiers` are used to tell the pack's own folders from a wrapping one in archives.
+    pub fn open(path: &Path, cache_dir: Option<&Path>, tiers: &Tiers) -> Result<Self> {
+        let zip = if is_archive(path) {
+            Some(ZipContents::open(path, tiers)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            zip,
+            cache: cache_dir.map(PcmCache::new),
+        })
+    }
+
+    /// Paths of the entries directly inside `dir`, sorted, or `None` if it isn't a
+    /// directory.
+    ///
+    /// The order decides the index of every sound, so it must not depend on the file
+    /// system or seeded renders differ between machines.
+    pub fn read_dir(&self, dir: &Path) -> Option<Vec<PathBuf>> {
+        match &self.zip {
+            None => {
+                let entries = dir.read_dir().ok()?;
+                let mut paths: Vec<PathBuf> = entries
+                    .filter_map(|entry| {
+                        entry
+                            .map_err(|e| log::warn!("error in directory entry: {e}"))
+                            .ok()
+                    })
+                    .map(|entry| entry.path())
+                    .collect();
+                paths.sort();
+                Some(paths)
+            }
+            Some(zip) => {
+                if !zip.dirs.contains(dir) {
+                    return None;
+                }
+                let children = zip.dirs.iter().chain(zip.files.keys());
+                let mut paths: Vec<PathBuf> = children
+                    .filter(|path| path.parent() == Some(dir))
+                    .cloned()
+                    .collect();
+                paths.sort();
+                Some(paths)
+            }
+        }
+    }
+
+    pub fn read(&self, path: &Path) -> Option<Vec<u8>> {
+        match &self.zip {
+            None => std::fs::read(path).ok(),
+            Some(zip) => zip.files.get(path).cloned(),
+        }
+    }
+
+    pub fn is_file(&self, path: &Path) -> bool {
+        match &self.zip {
+            None => path.is_file(),
+            Some(zip) => zip.files.contains_key(path),
+        }
+    }
+
+    fn cache_key<'a>(&self, path: &'a Path) -> Option<CacheKey<'a>> {
+        match &self.zip {
+            None => CacheKey::for_file(path),
+            Some(zip) => Some(CacheKey {
+                path,
+                size: zip.files.get(path)?.len() as u64,
+                modified: zip.modified,
+            }),
+        }
+    }
+
+    fn decode(&self, path: &Path) -> Result<Sound> {
+        match &self.zip {
+            None => Ok(Sound::from_path(path)?),
+            Some(zip) => {
+                let data = zip.files.get(path).context("file not found in zip")?;
+                Ok(Sound::from_cursor(Cursor::new(data.clone()))?)
+            }
+        }
+    }
+
+    pub fn load_sound(&self, path: &Path) -> Result<SoundWrapper> {
+        let cached = self.cache.as_ref().zip(self.cache_key(path));
+        if let Some((cache, key)) = &cached {
+            if let Some(sound) = cache.get(key) {
+                return Ok(SoundWrapper::new(path, sound));
+            }
+        }
+        let sound = self.decode(path)?;
+        if let Some((cache, key)) = &cached {
+            let _ = cache
+                .put(key, &sound)
+                .map_err(|e| log::warn!("failed to cache {path:?}: {e}"));
+        }
+        Ok(SoundWrapper::new(path, sound))
+    }
+
+    /// Delete the least recently used cached sounds if the cache has grown too large.
+    pub fn evict_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.evict(MAX_CACHE_SIZE);
+        }
+    }
+
+    /// Load many sounds at once, decoding on all cores.
+    pub fn load_sounds(&self, paths: &[PathBuf]) -> Vec<Result<SoundWrapper>> {
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        if threads <= 1 || paths.len() <= 1 {
+            return paths.iter().map(|path| self.load_sound(path)).collect();
+        }
+        let chunk_size = paths.len().div_ceil(threads);
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|path| self.load_sound(path))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn nested_zip() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        for name in [
+            "pack/player1/clicks/2.wav",
+            "pack/player1/clicks/1.wav",
+            "pack/player1/clicks/readme.txt",
+            "pack/cover.png",
+            "pack/clickpack.json",
+            "__MACOSX/pack/player1/clicks/._1.wav",
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        let data = zip.finish().unwrap().into_inner();
+
+        let dir = std::env::temp_dir().join(format!("dcd-source-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("pack.zip");
+        std::fs::write(&path, data).unwrap();
+        let src = ClickpackSource::open(&path, None, &Tiers::default()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // the wrapping `pack` folder is skipped
+        assert_eq!(
+            src.read_dir(&path).unwrap(),
+            [path.join(MANIFEST_FILENAME), path.join("player1")]
+        );
+        let clicks = path.join("player1/clicks");
+        assert_eq!(
+            src.read_dir(&clicks).unwrap(),
+            [clicks.join("1.wav"), clicks.join("2.wav")]
+        );
+        assert!(src.is_file(&clicks.join("1.wav")));
+        assert!(!src.is_file(&path.join("player1")));
+        assert!(src.read_dir(&path.join("pack")).is_none());
+        assert_eq!(
+            src.read(&clicks.join("2.wav")).unwrap(),
+            b"pack/player1/clicks/2.wav"
+        );
+    }
+}
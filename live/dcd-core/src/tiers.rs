@@ -0,0 +1,292 @@
+//! Click categories by the time since the previous action.
+//!
+//! Every tier has a threshold and a click and release folder. An action plays the first
+//! tier (highest threshold first) whose threshold is below the time since the previous
+//! action, or the last tier if none is. The default preset is hardclicks, clicks,
+//! softclicks and microclicks with thresholds from [`Timings`].
+
+use crate::clickpack::{ClickType, Timings};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct Tier {
+    pub name: String,
+    /// Seconds since the previous action above which this tier is used.
+    pub threshold: f64,
+    /// Folder with the push sounds, e.g. "hardclicks".
+    pub clicks: String,
+    /// Folder with the release sounds, e.g. "hardreleases".
+    pub releases: String,
+    /// Tiers to take sounds from if this one has none, closest first. Tiers that aren't
+    /// listed are tried after these.
+    #[serde(default)]
+    pub fallback: Vec<String>,
+}
+
+impl Tier {
+    fn new(name: &str, threshold: f64, fallback: [&str; 3]) -> Self {
+        let folder = if name == "regular" { "" } else { name };
+        Self {
+            name: name.to_string(),
+            threshold,
+            clicks: format!("{folder}clicks"),
+            releases: format!("{folder}releases"),
+            fallback: fallback.map(str::to_string).to_vec(),
+        }
+    }
+}
+
+/// Lowercase letters of a folder name without a trailing "s", so that "Soft Clicks",
+/// "soft_click" and "softclicks" are the same folder.
+fn normalize_folder_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    match name.strip_suffix('s') {
+        Some(name) => name.to_string(),
+        None => name,
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tiers {
+    /// Sorted by threshold, highest first.
+    tiers: Vec<Tier>,
+    /// Tier indices to try for each tier, starting with itself.
+    order: Vec<Vec<usize>>,
+}
+
+impl Default for Tiers {
+    fn default() -> Self {
+        Self::preset(&Timings::default())
+    }
+}
+
+impl Tiers {
+    /// Use `tiers`, or the default preset if the list is empty.
+    pub fn new(mut tiers: Vec<Tier>) -> Self {
+        if tiers.is_empty() {
+            return Self::default();
+        }
+        tiers.sort_by(|a, b| b.threshold.total_cmp(&a.threshold));
+        let order = tiers
+            .iter()
+            .enumerate()
+            .map(|(i, tier)| {
+                let mut order = vec![i];
+                let fallback = tier
+                    .fallback
+                    .iter()
+                    .filter_map(|name| tiers.iter().position(|t| &t.name == name));
+                for j in fallback.chain(0..tiers.len()) {
+                    if !order.contains(&j) {
+                        order.push(j);
+                    }
+                }
+                order
+            })
+            .collect();
+        Self { tiers, order }
+    }
+
+    /// The hardclicks, clicks, softclicks and microclicks tiers.
+    pub fn preset(timings: &Timings) -> Self {
+        Self::new(vec![
+            Tier::new("hard", timings.hard, ["regular", "soft", "micro"]),
+            Tier::new("regular", timings.regular, ["hard", "soft", "micro"]),
+            Tier::new("soft", timings.soft, ["micro", "regular", "hard"]),
+            Tier::new("micro", 0.0, ["soft", "regular", "hard"]),
+        ])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Tier> {
+        self.tiers.iter()
+    }
+
+    /// Category of an action `time` after the previous one, in the unit of the thresholds
+    /// (seconds unless timings are set in frames).
+    pub fn classify(&self, push: bool, time: f64) -> ClickType {
+        let tier = self
+            .tiers
+            .iter()
+            .position(|tier| time > tier.threshold)
+            .unwrap_or(self.tiers.len() - 1);
+        if push {
+            ClickType::Click(tier)
+        } else {
+            ClickType::Release(tier)
+        }
+    }
+
+    /// Categories to take a sound from for `typ`, best first: the fallback tiers of the
+    /// same kind, then of the other kind (releases for clicks and the other way around).
+    pub fn preferred(&self, typ: ClickType) -> impl Iterator<Item = ClickType> + '_ {
+        let (tier, release) = match typ {
+            ClickType::Click(tier) => (tier, false),
+            ClickType::Release(tier) => (tier, true),
+            ClickType::None => (usize::MAX, false),
+        };
+        let order = self.order.get(tier).map_or(&[][..], Vec::as_slice);
+        let same = order.iter().map(move |&t| ClickType::new(t, release));
+        let other = order.iter().map(move |&t| ClickType::new(t, !release));
+        same.chain(other)
+    }
+
+    /// Every category with its folder name, in the order of [`ClickType::index`].
+    pub fn categories(&self) -> impl Iterator<Item = (ClickType, &str)> {
+        self.tiers.iter().enumerate().flat_map(|(i, tier)| {
+            [
+                (ClickType::Click(i), tier.clicks.as_str()),
+                (ClickType::Release(i), tier.releases.as_str()),
+            ]
+        })
+    }
+
+    pub fn folder_name(&self, typ: ClickType) -> &str {
+        match typ {
+            ClickType::Click(i) => self.tiers.get(i).map_or("none", |t| &t.clicks),
+            ClickType::Release(i) => self.tiers.get(i).map_or("none", |t| &t.releases),
+            ClickType::None => "none",
+        }
+    }
+
+    /// Category of a sound folder, e.g. "softclicks", "soft_clicks", "Soft Click".
+    pub fn category_from_folder_name(&self, name: &str) -> Option<ClickType> {
+        let name = normalize_folder_name(name);
+        self.categories()
+            .find(|(_, folder)| normalize_folder_name(folder) == name)
+            .map(|(typ, _)| typ)
+    }
+
+    /// Category a layer folder like "clicks_layer" or "Soft Clicks Layer 2" belongs to.
+    pub fn layer_from_folder_name(&self, name: &str) -> Option<ClickType> {
+        let name = normalize_folder_name(name);
+        let category = name.strip_suffix("layer")?;
+        self.category_from_folder_name(category)
+    }
+
+    /// Whether `other` loads sounds from the same folders, i.e. a clickpack loaded with
+    /// one doesn't have to be reloaded for the other.
+    pub fn same_folders(&self, other: &Self) -> bool {
+        self.categories().eq(other.categories())
+    }
+
+    /// Check a custom tier list, returning a description of every problem found.
+    pub fn validate(tiers: &[Tier]) -> Vec<String> {
+        let mut problems = vec![];
+        for (i, tier) in tiers.iter().enumerate() {
+            if !(tier.threshold.is_finite() && tier.threshold >= 0.0) {
+                problems.push(format!(
+                    "tier {:?} threshold ({}) must be a non-negative number",
+                    tier.name, tier.threshold
+                ));
+            }
+            if tiers[..i].iter().any(|t| t.name == tier.name) {
+                problems.push(format!("tier {:?} is listed twice", tier.name));
+            }
+            for name in &tier.fallback {
+                if !tiers.iter().any(|t| &t.name == name) {
+                    problems.push(format!(
+                        "tier {:?} falls back to unknown tier {name:?}",
+                        tier.name
+                    ));
+                }
+            }
+        }
+        let mut folders: Vec<String> = tiers
+            .iter()
+            .flat_map(|t| [&t.clicks, &t.releases])
+            .map(|f| normalize_folder_name(f))
+            .collect();
+        folders.sort();
+        for pair in folders.windows(2) {
+            if pair[0] == pair[1] {
+                problems.push(format!(
+                    "folder {:?} is used by more than one tier",
+                    pair[0]
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_matches_timings() {
+        let tiers = Tiers::default();
+        assert_eq!(tiers.classify(true, 3.0), ClickType::Click(0));
+        assert_eq!(tiers.classify(false, 0.5), ClickType::Release(1));
+        assert_eq!(tiers.classify(true, 0.1), ClickType::Click(2));
+        assert_eq!(tiers.classify(true, 0.01), ClickType::Click(3));
+        assert_eq!(tiers.classify(true, 0.0), ClickType::Click(3));
+    }
+
+    #[test]
+    fn preset_fallback_order() {
+        let tiers = Tiers::default();
+        let names: Vec<&str> = tiers
+            .preferred(ClickType::Release(2))
+            .map(|typ| tiers.folder_name(typ))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "softreleases",
+                "microreleases",
+                "releases",
+                "hardreleases",
+                "softclicks",
+                "microclicks",
+                "clicks",
+                "hardclicks"
+            ]
+        );
+    }
+
+    #[test]
+    fn folder_names() {
+        let tiers = Tiers::default();
+        let find = |name| tiers.category_from_folder_name(name);
+        assert_eq!(find("Soft Clicks"), Some(ClickType::Click(2)));
+        assert_eq!(find("micro_release"), Some(ClickType::Release(3)));
+        assert_eq!(find("clicks"), Some(ClickType::Click(1)));
+        assert_eq!(find("taps"), None);
+        assert_eq!(find("clicks_layer"), None);
+        assert_eq!(
+            tiers.layer_from_folder_name("Soft Clicks Layer 2"),
+            Some(ClickType::Click(2))
+        );
+        assert_eq!(
+            tiers.layer_from_folder_name("releases_layers"),
+            Some(ClickType::Release(1))
+        );
+        assert_eq!(tiers.layer_from_folder_name("clicks"), None);
+    }
+
+    #[test]
+    fn custom_tiers() {
+        let tier = |name: &str, threshold| Tier {
+            name: name.to_string(),
+            threshold,
+            clicks: format!("{name}taps"),
+            releases: format!("{name}lifts"),
+            fallback: vec![],
+        };
+        let tiers = Tiers::new(vec![tier("ultrasoft", 0.0), tier("hard", 1.0)]);
+        assert_eq!(tiers.classify(true, 2.0), ClickType::Click(0));
+        assert_eq!(tiers.classify(true, 0.5), ClickType::Click(1));
+        assert_eq!(
+            tiers.category_from_folder_name("ultrasoft_lifts"),
+            Some(ClickType::Release(1))
+        );
+        assert!(Tiers::validate(&[tier("a", 0.0), tier("b", 1.0)]).is_empty());
+        let problems = Tiers::validate(&[tier("a", 0.0), tier("a", 1.0)]);
+        assert!(problems.iter().any(|p| p.contains("listed twice")));
+    }
+}
@@ -0,0 +1,375 @@
+//! Frame timing of the game. Frame times are collected once per game update, which is
+//! used to tell a steady (recording) frame rate apart from a jittery (live) one and to
+//! correct the click time when frames drift from the target frame rate.
+//!
+//! [`TimeUnit`] lets click timings be set in physics ticks instead of seconds, and
+//! [`TickClock`] counts the game time in ticks.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Physics ticks per second of the game without a TPS bypass.
+pub const DEFAULT_TPS: f64 = 240.0;
+
+/// Frame times kept if no other capacity is set.
+pub const DEFAULT_CAPACITY: usize = 120;
+
+/// Frames needed before the pacing is detected.
+const MIN_PACING_FRAMES: usize = 30;
+
+/// Frame time variance (in seconds squared) below which the game runs steadily and above
+/// which it runs jittery.
+const STABLE_VARIANCE: f64 = 0.0001;
+const VARIABLE_VARIANCE: f64 = 0.001;
+
+/// Largest time correction in seconds, in either direction.
+const MAX_CORRECTION: f64 = 0.02;
+
+/// How steadily the game produces frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FramePacing {
+    /// Not enough frames yet.
+    #[default]
+    Unknown,
+    /// Frame times barely change, like when recording with a fixed frame rate.
+    Stable,
+    /// Frame times jump around, like in live play.
+    Variable,
+    Mixed,
+}
+
+/// What click timings and death release delays are measured in.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeUnit {
+    #[default]
+    Seconds,
+    /// Physics ticks of the game.
+    Frames,
+}
+
+impl TimeUnit {
+    /// `secs` in this unit, at `tps` ticks per second.
+    pub fn secs_to_unit(self, secs: f64, tps: f64) -> f64 {
+        match self {
+            Self::Seconds => secs,
+            Self::Frames => secs * tps,
+        }
+    }
+
+    /// `value` in this unit in seconds, at `tps` ticks per second.
+    pub fn unit_to_secs(self, value: f64, tps: f64) -> f64 {
+        match self {
+            Self::Seconds => value,
+            Self::Frames => value / tps,
+        }
+    }
+}
+
+/// Game time counted in physics ticks instead of measured with a wall clock, so the same
+/// inputs (e.g. from a macro) get the same timestamps on every run.
+///
+/// Ticks come from the game if it reports them, otherwise they're derived from frame
+/// times. Ticks keep the length they had when they happened if the tick rate changes.
+#[derive(Clone, Debug)]
+pub struct TickClock {
+    ticks: u64,
+    /// Fraction of a tick left over from the frame times.
+    partial: f64,
+    /// The game reports every tick, frame times are ignored.
+    exact: bool,
+    tps: f64,
+    /// Seconds and ticks counted when the tick rate last changed.
+    rate_change: (f64, u64),
+}
+
+impl Default for TickClock {
+    fn default() -> Self {
+        Self {
+            ticks: 0,
+            partial: 0.0,
+            exact: false,
+            tps: DEFAULT_TPS,
+            rate_change: (0.0, 0),
+        }
+    }
+}
+
+impl TickClock {
+    /// Start counting from zero, on level start and restart.
+    pub fn reset(&mut self) {
+        self.ticks = 0;
+        self.partial = 0.0;
+        // the next level may run without reported ticks
+        self.exact = false;
+        self.rate_change = (0.0, 0);
+    }
+
+    /// The game runs at `tps` ticks per second from now on.
+    pub fn set_tps(&mut self, tps: f64) {
+        if tps == self.tps || !(tps.is_finite() && tps > 0.0) {
+            return;
+        }
+        self.rate_change = (self.time(), self.ticks);
+        self.partial *= tps / self.tps;
+        self.tps = tps;
+    }
+
+    /// The game ran one physics tick.
+    pub fn tick(&mut self) {
+        self.exact = true;
+        self.ticks += 1;
+    }
+
+    /// The game ran a frame of `dt` seconds. Ignored once the game reported a tick.
+    pub fn advance(&mut self, dt: f64) {
+        if self.exact || dt.is_nan() || dt <= 0.0 {
+            return;
+        }
+        // frame times are rounded by the game, don't lose a tick to that
+        self.partial += dt * self.tps;
+        let whole = (self.partial + 1e-6).floor();
+        self.partial = (self.partial - whole).max(0.0);
+        self.ticks += whole as u64;
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Seconds since the level started.
+    pub fn time(&self) -> f64 {
+        let (secs, ticks) = self.rate_change;
+        secs + (self.ticks - ticks) as f64 / self.tps
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TimingEngine {
+    /// Last frame times in seconds, oldest first.
+    frames: VecDeque<f64>,
+    capacity: usize,
+    /// Frame rate the game is supposed to run at. Detected from the frame times if unset.
+    target_fps: Option<f64>,
+    drift: f64,
+    correction: f64,
+}
+
+impl Default for TimingEngine {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl TimingEngine {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            target_fps: None,
+            drift: 0.0,
+            correction: 0.0,
+        }
+    }
+
+    /// Forget all frames and the correction, on level start and restart.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.drift = 0.0;
+        self.correction = 0.0;
+    }
+
+    /// Keep the last `capacity` frame times from now on.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.frames.len() > self.capacity {
+            self.frames.pop_front();
+        }
+    }
+
+    /// Frame rate the game runs at, or `None` to detect it from the frame times.
+    pub fn set_target_fps(&mut self, fps: Option<f64>) {
+        self.target_fps = fps.filter(|&fps| fps > 0.0);
+    }
+
+    /// Record a game update that took `dt` seconds. Non-positive frame times (pauses,
+    /// the first frame) are ignored.
+    pub fn push_frame(&mut self, dt: f64) {
+        if dt.is_nan() || dt <= 0.0 {
+            return;
+        }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(dt);
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn average_frame_time(&self) -> Option<f64> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        Some(self.frames.iter().sum::<f64>() / self.frames.len() as f64)
+    }
+
+    /// Seconds one frame should take: from the target frame rate if it's set, the median
+    /// frame time otherwise.
+    pub fn target_frame_time(&self) -> Option<f64> {
+        if let Some(fps) = self.target_fps {
+            return Some(1.0 / fps);
+        }
+        if self.frames.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = self.frames.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        Some(sorted[sorted.len() / 2])
+    }
+
+    /// Frame rate the game runs at, see [`TimingEngine::target_frame_time`].
+    pub fn fps(&self) -> Option<f64> {
+        self.target_frame_time().map(|t| 1.0 / t)
+    }
+
+    pub fn pacing(&self) -> FramePacing {
+        if self.frames.len() < MIN_PACING_FRAMES {
+            return FramePacing::Unknown;
+        }
+        let avg = self.average_frame_time().unwrap_or_default();
+        let variance = self
+            .frames
+            .iter()
+            .map(|&dt| (dt - avg).powi(2))
+            .sum::<f64>()
+            / self.frames.len() as f64;
+        if variance < STABLE_VARIANCE {
+            FramePacing::Stable
+        } else if variance > VARIABLE_VARIANCE {
+            FramePacing::Variable
+        } else {
+            FramePacing::Mixed
+        }
+    }
+
+    /// Accumulate how far the average frame time is off from the target, and set the
+    /// correction to `factor` times that.
+    pub fn update_correction(&mut self, factor: f64) {
+        let (Some(avg), Some(target)) = (self.average_frame_time(), self.target_frame_time())
+        else {
+            return;
+        };
+        self.drift += avg - target;
+        self.correction = (self.drift * factor).clamp(-MAX_CORRECTION, MAX_CORRECTION);
+    }
+
+    /// Seconds to add to the game time.
+    pub fn correction(&self) -> f64 {
+        self.correction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_frame_rate_and_pacing() {
+        let mut timing = TimingEngine::default();
+        assert_eq!(timing.fps(), None);
+        for _ in 0..29 {
+            timing.push_frame(1.0 / 240.0);
+        }
+        assert_eq!(timing.pacing(), FramePacing::Unknown);
+        timing.push_frame(1.0 / 240.0);
+        timing.push_frame(0.0);
+        assert_eq!(timing.frames(), 30);
+        assert_eq!(timing.pacing(), FramePacing::Stable);
+        assert!((timing.fps().unwrap() - 240.0).abs() < 1e-6);
+
+        // frames alternating between 10 and 80 ms
+        for i in 0..DEFAULT_CAPACITY {
+            timing.push_frame(if i % 2 == 0 { 0.01 } else { 0.08 });
+        }
+        assert_eq!(timing.frames(), DEFAULT_CAPACITY);
+        assert_eq!(timing.pacing(), FramePacing::Variable);
+
+        timing.set_target_fps(Some(60.0));
+        assert!((timing.target_frame_time().unwrap() - 1.0 / 60.0).abs() < 1e-12);
+
+        timing.reset();
+        assert_eq!(timing.frames(), 0);
+        assert_eq!(timing.pacing(), FramePacing::Unknown);
+        // the target frame rate stays
+        assert_eq!(timing.fps(), Some(60.0));
+    }
+
+    #[test]
+    fn corrects_drift() {
+        let mut timing = TimingEngine::new(60);
+        // a game running at its own frame rate doesn't drift
+        for _ in 0..60 {
+            timing.push_frame(1.0 / 144.0);
+            timing.update_correction(0.1);
+        }
+        assert!(timing.correction().abs() < 1e-9);
+
+        // a game running at 50 fps that should run at 60 drifts behind
+        timing.set_target_fps(Some(60.0));
+        for _ in 0..60 {
+            timing.push_frame(1.0 / 50.0);
+        }
+        timing.update_correction(0.1);
+        let expected = (1.0 / 50.0 - 1.0 / 60.0) * 0.1;
+        assert!((timing.correction() - expected).abs() < 1e-9);
+        for _ in 0..1000 {
+            timing.update_correction(0.1);
+        }
+        assert_eq!(timing.correction(), MAX_CORRECTION);
+
+        timing.set_capacity(10);
+        assert_eq!(timing.frames(), 10);
+        timing.reset();
+        assert_eq!(timing.correction(), 0.0);
+    }
+
+    #[test]
+    fn tick_clock() {
+        let mut clock = TickClock::default();
+        // 60 fps frames at 240 tps, with the rounding of an f32 frame time
+        for _ in 0..60 {
+            clock.advance((1.0f32 / 60.0) as f64);
+        }
+        assert_eq!(clock.ticks(), 240);
+        assert_eq!(clock.time(), 1.0);
+
+        clock.reset();
+        clock.advance(1.5 / 240.0);
+        clock.advance(1.5 / 240.0);
+        assert_eq!(clock.ticks(), 3);
+
+        // reported ticks replace the frame times
+        clock.reset();
+        clock.tick();
+        clock.advance(1.0);
+        clock.tick();
+        assert_eq!(clock.ticks(), 2);
+        assert_eq!(clock.time(), 2.0 / 240.0);
+
+        // until the next level, which may not report them
+        clock.reset();
+        clock.advance(1.0);
+        assert_eq!(clock.ticks(), 240);
+
+        // earlier ticks keep their length when the tick rate changes
+        clock.set_tps(480.0);
+        clock.advance(0.5);
+        assert_eq!(clock.ticks(), 480);
+        assert_eq!(clock.time(), 1.5);
+        clock.reset();
+        assert_eq!(clock.time(), 0.0);
+        clock.advance(1.0);
+        assert_eq!(clock.time(), 1.0);
+    }
+}
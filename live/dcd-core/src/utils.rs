@@ -0,0 +1,54 @@
+use std::ops::RangeInclusive;
+
+pub fn capitalize_first_letter(s: &str) -> String {
+    let mut c = s.chars();
+    match c.next() {
+        None => String::new(),
+        Some(f) => f.to_uppercase().collect::<String>() + c.as_str(),
+    }
+}
+
+#[inline]
+pub fn f64_range(rng: &mut fastrand::Rng, range: RangeInclusive<f64>) -> f64 {
+    rng.f64() * (range.end() - range.start()) + range.start()
+}
+
+/// Left and right channel gains for `pan` from -1 (left) to 1 (right). Balance law: the
+/// near channel stays at full volume and the far one is turned down.
+pub fn pan_gains(pan: f64) -> (f32, f32) {
+    let pan = pan.clamp(-1.0, 1.0) as f32;
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+
+/// Panning and volume factor that give a kittyaudio `Sound` the gains of [`pan_gains`].
+/// kittyaudio pans with `left * sqrt(2 - 2x)` and `right * sqrt(2x)` for a panning `x`
+/// from 0 to 1, applied while mixing, so the sound doesn't have to be copied.
+pub fn mixer_panning(pan: f64) -> (f32, f32) {
+    let (left, right) = pan_gains(pan);
+    let power = left * left + right * right;
+    if power == 0.0 {
+        return (0.5, 0.0);
+    }
+    (right * right / power, (power / 2.0).sqrt())
+}
+
+/// FNV-1a, for hashes that are stored on disk (std's hasher may change between releases).
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl std::hash::Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 = (self.0 ^ *b as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Polling for changes to loaded clickpacks, so they can be reloaded while testing.
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+/// Size and modification time of every file in a clickpack.
+#[derive(Clone, Default, PartialEq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, (u64, Option<SystemTime>)>,
+}
+
+impl Snapshot {
+    /// Record `path`, which can be a directory (walked recursively) or a single archive.
+    pub fn take(path: &Path) -> Self {
+        let mut snapshot = Self::default();
+        snapshot.add(path);
+        snapshot
+    }
+
+    fn add(&mut self, path: &Path) {
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return;
+        };
+        if metadata.is_dir() {
+            let Ok(entries) = path.read_dir() else {
+                return;
+            };
+            for entry in entries.flatten() {
+                self.add(&entry.path());
+            }
+        } else {
+            self.files.insert(
+                path.to_path_buf(),
+                (metadata.len(), metadata.modified().ok()),
+            );
+        }
+    }
+
+    /// What changed between `self` and the newer snapshot `new`.
+    pub fn diff(&self, new: &Self) -> Changes {
+        let mut changes = Changes::default();
+        for (path, meta) in &new.files {
+            match self.files.get(path) {
+                None => changes.added.push(path.clone()),
+                Some(old) if old != meta => changes.modified.push(path.clone()),
+                _ => {}
+            }
+        }
+        for path in self.files.keys() {
+            if !new.files.contains_key(path) {
+                changes.removed.push(path.clone());
+            }
+        }
+        changes
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Changes {
+    pub added: Vec<PathBuf>,
+    pub removed: Vec<PathBuf>,
+    pub modified: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Merge `other` into `self`, for changes that happen over several polls.
+    fn extend(&mut self, other: Changes) {
+        for (list, new) in [
+            (&mut self.added, other.added),
+            (&mut self.removed, other.removed),
+            (&mut self.modified, other.modified),
+        ] {
+            for path in new {
+                if !list.contains(&path) {
+                    list.push(path);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Changes {
+    /// e.g. "2 added, 1 modified"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = [
+            (self.added.len(), "added"),
+            (self.removed.len(), "removed"),
+            (self.modified.len(), "modified"),
+        ]
+        .into_iter()
+        .filter(|(n, _)| *n != 0)
+        .map(|(n, what)| format!("{n} {what}"))
+        .collect();
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+#[derive(Default)]
+struct WatchState {
+    paths: Vec<PathBuf>,
+    snapshots: Option<Vec<Snapshot>>,
+    /// Changes seen so far, reported once the files stop changing.
+    pending: Changes,
+    ready: Option<Changes>,
+}
+
+/// Polls the watched paths on a background thread.
+pub struct ClickpackWatcher {
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl ClickpackWatcher {
+    pub fn spawn(interval: Duration) -> Self {
+        let state = Arc::new(Mutex::new(WatchState::default()));
+        let thread_state = Arc::downgrade(&state);
+        std::thread::spawn(move || {
+            // stops when the watcher is dropped
+            while let Some(state) = thread_state.upgrade() {
+                Self::poll(&state);
+                drop(state);
+                std::thread::sleep(interval);
+            }
+        });
+        Self { state }
+    }
+
+    fn poll(state: &Mutex<WatchState>) {
+        let paths = state.lock().unwrap().paths.clone();
+        // walk the files without holding the lock
+        let snapshots: Vec<Snapshot> = paths.iter().map(|p| Snapshot::take(p)).collect();
+
+        let mut state = state.lock().unwrap();
+        if state.paths != paths {
+            return; // changed while we were walking, start over next time
+        }
+        let Some(prev) = state.snapshots.replace(snapshots.clone()) else {
+            return; // first poll after `watch`
+        };
+        let mut changes = Changes::default();
+        for (old, new) in prev.iter().zip(&snapshots) {
+            changes.extend(old.diff(new));
+        }
+        if changes.is_empty() {
+            if !state.pending.is_empty() {
+                state.ready = Some(std::mem::take(&mut state.pending));
+            }
+        } else {
+            state.pending.extend(changes);
+        }
+    }
+
+    /// Watch `paths` instead of the previous ones. Does nothing if they're the same.
+    pub fn watch(&self, paths: Vec<PathBuf>) {
+        let mut state = self.state.lock().unwrap();
+        if state.paths != paths {
+            *state = WatchState {
+                paths,
+                ..Default::default()
+            };
+        }
+    }
+
+    /// Changes since the last call, once files have stopped changing for one poll.
+    pub fn take_changes(&self) -> Option<Changes> {
+        self.state.lock().unwrap().ready.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(files: &[(&str, u64)]) -> Snapshot {
+        Snapshot {
+            files: files
+                .iter()
+                .map(|(path, size)| (PathBuf::from(path), (*size, None)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn diff() {
+        let old = snapshot(&[("a.wav", 1), ("b.wav", 2), ("c.wav", 3)]);
+        let new = snapshot(&[("a.wav", 1), ("b.wav", 5), ("d.wav", 4)]);
+        let changes = old.diff(&new);
+        assert_eq!(changes.added, [PathBuf::from("d.wav")]);
+        assert_eq!(changes.removed, [PathBuf::from("c.wav")]);
+        assert_eq!(changes.modified, [PathBuf::from("b.wav")]);
+        assert_eq!(changes.to_string(), "1 added, 1 removed, 1 modified");
+        assert!(old.diff(&old).is_empty());
+    }
+}
@@ -1,9 +1,22 @@
+use crate::bus::ClickBus;
 #[cfg(not(feature = "geode"))]
 use crate::{game::PlayLayer, hooks};
 
-use crate::{
-    clickpack::{Button, ClickType, Clickpack, LoadClickpackFor, Pitch, Timings, VolumeSettings, SoundFilterCriteria, SoundQuality, SoundWrapper},
+use dcd_core::{
+    clickpack::{
+        Button, ClickType, Clickpack, LoadClickpackFor, SoundFilterCriteria, SoundQuality, Timings,
+    },
+    config,
+    dsp::{EqBand, FilterKind, SoundProfile},
+    fft, reverb,
+    engine::{ClickEngine, ClickSettings},
+    manifest::ClickpackManifest,
+    report::Severity,
+    schedule::{ClickTiming, TimingStats},
+    source,
+    timing::{self, FramePacing, TickClock, TimeUnit, TimingEngine},
     utils,
+    watch::ClickpackWatcher,
 };
 use anyhow::Result;
 use egui::{
@@ -32,6 +45,23 @@ use std::{
 #[cfg(not(feature = "geode"))]
 use windows::Win32::System::Console::{AllocConsole, FreeConsole};
 
+/// Output frames analyzed for the waveform and spectrum, must be a power of two.
+const VISUALIZATION_FRAMES: usize = 2048;
+const WAVEFORM_POINTS: usize = 128;
+const SPECTRUM_BANDS: usize = 64;
+/// Level shown as an empty spectrum bar.
+const SPECTRUM_FLOOR_DB: f32 = -72.0;
+/// Spectrum heights per second.
+const PEAK_FALL_SPEED: f32 = 0.5;
+
+/// Largest spacing error, in seconds, for a click to count as in sync.
+const SYNC_TOLERANCE: f64 = 0.001;
+/// Test clicks played by the latency calibration, and the time between them.
+const CALIBRATION_CLICKS: usize = 16;
+const CALIBRATION_INTERVAL: Duration = Duration::from_millis(100);
+/// Give up on test clicks that weren't played after this long.
+const CALIBRATION_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Global bot state - using OnceLock for thread-safe initialization
 static BOT_INSTANCE: OnceLock<Arc<Mutex<Bot>>> = OnceLock::new();
 
@@ -52,7 +82,7 @@ impl BotWrapper {
         get_bot().lock().draw_ui(ctx);
     }
 
-    pub fn on_action(&self, button: crate::clickpack::Button, player2: bool, push: bool) {
+    pub fn on_action(&self, button: Button, player2: bool, push: bool) {
         unsafe { get_bot().lock().on_action(button, player2, push); }
     }
 
@@ -76,6 +106,10 @@ impl BotWrapper {
         unsafe { get_bot().lock().on_update(dt); }
     }
 
+    pub fn on_tick(&self) {
+        get_bot().lock().ticks.tick();
+    }
+
     pub fn play_noise(&self) {
         get_bot().lock().play_noise();
     }
@@ -104,12 +138,16 @@ impl BotWrapper {
         get_bot().lock().playlayer_time = value;
     }
 
+    pub fn set_tps(&self, tps: f64, fps: f64) {
+        get_bot().lock().set_tps(tps, fps);
+    }
+
     pub fn get_conf_show_console(&self) -> bool {
         get_bot().lock().conf.show_console
     }
 
     pub fn get_conf_force_player2_sounds(&self) -> bool {
-        get_bot().lock().conf.force_player2_sounds
+        get_bot().lock().conf.core.force_player2_sounds
     }
 
     pub fn get_conf_use_alternate_hook(&self) -> bool {
@@ -126,7 +164,11 @@ impl BotWrapper {
 
     // Access to clickpack field
     pub fn load_clickpack_from_path(&self, dir: &std::path::Path, load_for: LoadClickpackFor) -> anyhow::Result<()> {
-        get_bot().lock().clickpack.load_from_path(dir, load_for)
+        let mut bot = get_bot().lock();
+        bot.clickpack.cache_dir = Some(PathBuf::from(".dcd/cache"));
+        bot.clickpack.tiers = bot.conf.core.tiers();
+        bot.clickpack.analyze_loudness = bot.conf.core.volume_settings.normalize;
+        bot.clickpack.load_from_path(dir, load_for)
     }
 }
 
@@ -148,29 +190,6 @@ pub mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq)]
-pub struct Shortcuts {
-    pub toggle_menu: Shortcut,
-    pub toggle_bot: Shortcut,
-    pub toggle_noise: Shortcut,
-}
-
-impl Default for Shortcuts {
-    fn default() -> Self {
-        Self {
-            toggle_menu: Shortcut::new(
-                Some(KeyboardShortcut::new(Modifiers::NONE, Key::Num1)),
-                None,
-            ),
-            toggle_bot: Shortcut::new(
-                Some(KeyboardShortcut::new(Modifiers::NONE, Key::Num2)),
-                None,
-            ),
-            toggle_noise: Shortcut::NONE,
-        }
-    }
-}
-
 fn skip_serializing_selected_device(device: &str) -> bool {
     let is_default = if let Ok(name) = Device::Default.name() {
         name == device
@@ -190,9 +209,10 @@ pub enum ClickpackEnv {
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Env {
-    version: String,
-    clickpack_ord: Vec<(ClickpackEnv, LoadClickpackFor)>,
-    is_first_launch: bool,
+    /// Version of the front end that last wrote `.dcd/env.json`.
+    pub version: String,
+    pub clickpack_ord: Vec<(ClickpackEnv, LoadClickpackFor)>,
+    pub is_first_launch: bool,
     #[serde(
         default = "String::new",
         skip_serializing_if = "skip_serializing_selected_device"
@@ -203,7 +223,7 @@ pub struct Env {
 impl Default for Env {
     fn default() -> Self {
         Self {
-            version: built_info::PKG_VERSION.to_string(),
+            version: String::new(),
             clickpack_ord: vec![(ClickpackEnv::None, LoadClickpackFor::All)],
             is_first_launch: true, // overriden later
             selected_device: String::new(),
@@ -212,15 +232,21 @@ impl Default for Env {
 }
 
 impl Env {
-    pub fn load() -> Self {
+    /// Read `.dcd/env.json`, writing defaults if it's missing or broken.
+    ///
+    /// `version` is stored on the next save.
+    pub fn load(version: &str) -> Self {
         let _ = std::fs::create_dir_all(".dcd")
             .map_err(|e| log::error!("failed to create .dcd directory: {e}"));
 
         let path = Path::new(".dcd/env.json");
         if let Ok(f) = std::fs::File::open(path) {
-            let env = serde_json::from_reader(f);
+            let env = serde_json::from_reader::<_, Self>(f);
             if let Ok(env) = env {
-                return env;
+                return Self {
+                    version: version.to_string(),
+                    ..env
+                };
             } else if let Err(e) = env {
                 log::error!("failed to deserialize env: {e}");
             }
@@ -228,20 +254,18 @@ impl Env {
 
         // if we're here we failed
         log::warn!("failed to deserialize env, writing defaults");
-        if let Ok(f) = std::fs::File::create(path) {
-            let _ = serde_json::to_writer_pretty(f, &Self::default())
-                .map_err(|e| log::error!("failed to write env: {e}"));
-        }
-
-        Self::default()
+        let env = Self {
+            version: version.to_string(),
+            ..Self::default()
+        };
+        env.save();
+        env
     }
 
     pub fn save(&self) {
         log::info!("writing .dcd/env.json");
-        let mut env = self.clone();
-        env.version = built_info::PKG_VERSION.to_string();
         if let Ok(f) = std::fs::File::create(".dcd/env.json") {
-            let _ = serde_json::to_writer_pretty(f, &env)
+            let _ = serde_json::to_writer_pretty(f, self)
                 .map_err(|e| log::error!("failed to write env: {e}"));
         }
     }
@@ -257,12 +281,29 @@ impl Env {
         }
         self.save();
     }
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Shortcuts {
+    pub toggle_menu: Shortcut,
+    pub toggle_bot: Shortcut,
+    pub toggle_noise: Shortcut,
+}
 
-    // fn is_selected_by_path(&self) -> bool {
-    //     self.clickpack_ord
-    //         .iter()
-    //         .any(|c| matches!(c.0, ClickpackEnv::Path(_)))
-    // }
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Self {
+            toggle_menu: Shortcut::new(
+                Some(KeyboardShortcut::new(Modifiers::NONE, Key::Num1)),
+                None,
+            ),
+            toggle_bot: Shortcut::new(
+                Some(KeyboardShortcut::new(Modifiers::NONE, Key::Num2)),
+                None,
+            ),
+            toggle_noise: Shortcut::NONE,
+        }
+    }
 }
 
 const fn true_value() -> bool {
@@ -285,10 +326,6 @@ const fn default_audio_sample_rate() -> u32 {
     48000 // Default sample rate for enhanced audio
 }
 
-const fn default_sound_profile() -> u32 {
-    2 // Default sound profile: 1=Bass, 2=Balanced, 3=Treble, 4=Custom
-}
-
 const fn default_recording_buffer_size() -> u32 {
     240 // 4 seconds at 60fps for recording mode
 }
@@ -309,6 +346,10 @@ const fn default_sync_audio_latency() -> f64 {
     0.001 // 1ms latency for sync audio
 }
 
+const fn default_schedule_lookahead() -> f64 {
+    0.002 // on top of one audio buffer
+}
+
 #[inline]
 fn float_one<Num: emath::Numeric>() -> Num {
     Num::from_f64(1.0)
@@ -323,55 +364,6 @@ pub enum Stage {
     Options,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
-struct IgnoredClickTypes {
-    hardclicks: bool,
-    hardreleases: bool,
-    softclicks: bool,
-    softreleases: bool,
-    clicks: bool,
-    releases: bool,
-    microclicks: bool,
-    microreleases: bool,
-}
-
-impl IgnoredClickTypes {
-    #[inline]
-    const fn is_ignored(&self, typ: ClickType) -> bool {
-        match typ {
-            ClickType::HardClick => self.hardclicks,
-            ClickType::HardRelease => self.hardreleases,
-            ClickType::SoftClick => self.softclicks,
-            ClickType::SoftRelease => self.softreleases,
-            ClickType::Click => self.clicks,
-            ClickType::Release => self.releases,
-            ClickType::MicroClick => self.microclicks,
-            ClickType::MicroRelease => self.microreleases,
-            ClickType::None => true,
-        }
-    }
-
-    #[inline]
-    const fn any_ignored(&self) -> bool {
-        self.hardclicks
-            || self.hardreleases
-            || self.softclicks
-            || self.softreleases
-            || self.clicks
-            || self.releases
-            || self.microclicks
-            || self.microreleases
-    }
-}
-
-const fn death_release_delay_default() -> f64 {
-    0.001
-}
-
-const fn death_release_delay_offset_default() -> f64 {
-    0.13
-}
-
 #[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
 pub enum ToastVisibility {
     #[default]
@@ -393,24 +385,23 @@ impl ToastVisibility {
 
 #[derive(Serialize, Deserialize, Clone, PartialEq)]
 pub struct Config {
-    pub pitch_enabled: bool,
-    pub pitch: Pitch,
-    pub timings: Timings,
-    pub volume_settings: VolumeSettings,
+    #[serde(flatten)]
+    pub core: config::Config,
     #[serde(default = "Shortcuts::default")]
     pub shortcuts: Shortcuts,
-    #[serde(default = "true_value")]
-    pub enabled: bool,
     #[serde(default = "bool::default")]
     pub hidden: bool,
     #[serde(default = "default_buffer_size")]
     pub buffer_size: u32,
+    /// Start clicks at the exact frame matching their input time, a little later.
+    #[serde(default = "true_value")]
+    pub schedule_clicks: bool,
+    /// Seconds clicks are delayed by on top of one buffer when scheduling.
+    #[serde(default = "default_schedule_lookahead")]
+    pub schedule_lookahead: f64,
+    /// Count the level time in physics ticks instead of measuring it.
     #[serde(default = "bool::default")]
-    pub play_noise: bool,
-    #[serde(default = "float_one")]
-    pub noise_volume: f64,
-    #[serde(default = "bool::default")]
-    pub force_playing_platformer: bool,
+    pub tick_time: bool,
     #[serde(default = "bool::default")]
     pub use_alternate_hook: bool,
     #[serde(default = "bool::default")]
@@ -419,38 +410,12 @@ pub struct Config {
     pub stage: Stage,
     #[serde(default = "bool::default")]
     pub use_fmod: bool,
-    #[serde(default = "bool::default")]
-    pub cut_sounds: bool,
-    #[serde(default = "bool::default")]
-    pub cut_by_releases: bool,
-    #[serde(default = "float_one")]
-    pub click_speedhack: f64,
     // #[serde(default = "true_value")]
     // pub sync_speed_with_game: bool,
-    #[serde(default = "float_one")]
-    pub noise_speedhack: f64,
-    #[serde(default = "LoadClickpackFor::default")]
-    pub load_clickpack_for: LoadClickpackFor,
-    #[serde(default = "bool::default")]
-    pub decouple_platformer: bool,
     #[serde(default = "true_value")]
     pub autosave_config: bool,
     #[serde(default = "true_value")]
-    pub release_buttons_on_death: bool,
-    #[serde(default = "death_release_delay_default")]
-    pub death_release_delay: f64,
-    #[serde(default = "death_release_delay_offset_default")]
-    pub death_release_delay_offset: f64,
-    #[serde(default = "bool::default")]
-    pub death_release_delay_neg: bool,
-    #[serde(default = "bool::default")]
-    pub force_player2_sounds: bool,
-    #[serde(default = "bool::default")]
-    pub play_noise_when_disabled: bool,
-    #[serde(default = "IgnoredClickTypes::default")]
-    ignored_click_types: IgnoredClickTypes,
-    #[serde(default = "bool::default")]
-    pub use_ingame_time: bool,
+    pub hot_reload_clickpacks: bool,
     // Enhanced gametime and sync recording features
     #[serde(default = "bool::default")]
     pub instant_sync_mode: bool,
@@ -458,6 +423,9 @@ pub struct Config {
     pub enhanced_recording_sync: bool,
     #[serde(default = "bool::default")]
     pub adaptive_timing_correction: bool,
+    /// Frame rate the game is supposed to run at, detected from the frame times if 0.
+    #[serde(default = "f64::default")]
+    pub target_fps: f64,
     #[serde(default = "bool::default")]
     pub real_time_sync_display: bool,
     #[serde(default = "default_sync_threshold")]
@@ -473,13 +441,9 @@ pub struct Config {
     pub audio_sample_rate: u32,
     // Advanced audio sound selection features
     #[serde(default = "bool::default")]
-    pub advanced_sound_filtering: bool,
-    #[serde(default = "bool::default")]
     pub dynamic_sound_selection: bool,
     #[serde(default = "bool::default")]
     pub audio_enhancement_mode: bool,
-    #[serde(default = "default_sound_profile")]
-    pub sound_profile: u32,
     #[serde(default = "String::new")]
     pub preferred_audio_device: String,
     #[serde(default = "float_one")]
@@ -574,40 +538,24 @@ impl Config {
 impl Default for Config {
     fn default() -> Self {
         Self {
-            pitch_enabled: true,
-            pitch: Pitch::default(),
-            timings: Timings::default(),
-            volume_settings: VolumeSettings::default(),
+            core: config::Config::default(),
             shortcuts: Shortcuts::default(),
-            enabled: true,
             hidden: false,
             buffer_size: default_buffer_size(),
-            play_noise: false,
-            noise_volume: 1.0,
-            force_playing_platformer: false,
+            schedule_clicks: true,
+            schedule_lookahead: default_schedule_lookahead(),
+            tick_time: false,
             use_alternate_hook: false,
             show_console: false,
             stage: Stage::default(),
             use_fmod: false,
-            cut_sounds: false,
-            cut_by_releases: false,
-            click_speedhack: 1.0,
-            noise_speedhack: 1.0,
-            load_clickpack_for: LoadClickpackFor::All,
-            decouple_platformer: false,
             autosave_config: true,
-            release_buttons_on_death: true,
-            death_release_delay: death_release_delay_default(),
-            death_release_delay_offset: death_release_delay_offset_default(),
-            death_release_delay_neg: false,
-            force_player2_sounds: false,
-            play_noise_when_disabled: false,
-            ignored_click_types: IgnoredClickTypes::default(),
-            use_ingame_time: false,
+            hot_reload_clickpacks: true,
             // Enhanced gametime and sync recording features defaults
             instant_sync_mode: false,
             enhanced_recording_sync: false,
             adaptive_timing_correction: false,
+            target_fps: 0.0,
             real_time_sync_display: false,
             sync_quality_threshold: default_sync_threshold(),
             // Enhanced audio system defaults
@@ -638,10 +586,8 @@ impl Default for Config {
             sync_audio_latency: default_sync_audio_latency(),
             real_time_audio_analysis: false,
             audio_sync_correction: false,
-            advanced_sound_filtering: false,
             dynamic_sound_selection: false,
             audio_enhancement_mode: false,
-            sound_profile: default_sound_profile(),
             preferred_audio_device: String::new(),
             audio_visualization_enabled: false,
             waveform_display_mode: WaveformDisplayMode::default(),
@@ -705,70 +651,23 @@ impl Config {
     }
 }
 
-#[derive(Default, Clone, Copy)]
-struct ClickTime {
-    time: f64,
-    typ: ClickType,
-}
-
-#[derive(Default)]
-pub struct ClickTimes {
-    jump: [ClickTime; 2],  // 2 players
-    left: [ClickTime; 2],  // 2 players
-    right: [ClickTime; 2], // 2 players
-}
-
-impl ClickTimes {
-    #[inline]
-    fn set_time(&mut self, button: Button, player2: bool, t: ClickTime, decouple: bool) {
-        match button {
-            Button::Jump => self.jump[player2 as usize] = t,
-            Button::Left => self.left[player2 as usize] = t,
-            Button::Right => {
-                // TODO:
-                // WTF:  `(if decouple { self.right } else { self.left })[player2 as usize] = time``
-                //       causes a Rust miscompilation if `decouple` is false???
-                if decouple {
-                    self.right[player2 as usize] = t;
-                } else {
-                    self.left[player2 as usize] = t;
-                }
-            }
-        }
-    }
-
-    #[inline]
-    const fn get_prev_time(&self, button: Button, player2: bool, decouple: bool) -> ClickTime {
-        match button {
-            Button::Jump => self.jump[player2 as usize],
-            Button::Left => self.left[player2 as usize],
-            Button::Right => {
-                if decouple {
-                    self.right[player2 as usize]
-                } else {
-                    self.left[player2 as usize]
-                }
-            }
-        }
-    }
-}
-
 pub struct Bot {
     pub conf: Config,
     pub prev_conf: Config,
     pub last_conf_save: Instant,
     pub mixer: Mixer,
+    pub bus: ClickBus,
+    pub timing: TimingEngine,
+    pub ticks: TickClock,
     #[cfg(not(feature = "geode"))]
     pub playlayer: PlayLayer,
-    pub prev_times: ClickTimes,
+    pub engine: ClickEngine,
     pub is_loading_clickpack: Arc<AtomicBool>,
-    pub prev_pitch: f64,
-    pub prev_volume: f64,
-    pub prev_spam_offset: f64,
     pub buffer_size_changed: bool,
     pub noise_sound: Option<SoundHandle>,
     pub clickpacks: Vec<String>,
     pub last_clickpack_reload: Instant,
+    pub clickpack_watcher: ClickpackWatcher,
     // pub system: *mut FMOD_SYSTEM,
     // pub channel: *mut FMOD_CHANNEL,
     pub env: Env,
@@ -778,6 +677,10 @@ pub struct Bot {
     pub startup_buffer_size: u32,
     pub is_in_level: bool,
     pub playlayer_time: f64,
+    /// Physics ticks per second of the game.
+    pub tps: f64,
+    /// Frames per second the game renders at, if it told us.
+    pub game_fps: Option<f64>,
     pub clickpack: Clickpack,
     pub first_launch_dialog_timeout: f32,
     pub level_start: Instant,
@@ -800,6 +703,7 @@ pub struct Bot {
     pub audio_spectrum_data: Vec<f32>,
     pub waveform_history: Vec<Vec<f32>>,
     pub spectrum_history: Vec<Vec<f32>>,
+    pub spectrum_peaks: Vec<f32>,
     pub visualization_fps: f32,
     pub visualization_smoothing: f32,
     // Sync performance analytics
@@ -807,10 +711,14 @@ pub struct Bot {
     pub sync_analytics_enabled: bool,
     pub sync_quality_trends: Vec<f64>,
     pub sync_latency_trends: Vec<f64>,
-    pub sync_accuracy_trends: Vec<f64>,
+    pub sync_error_trends: Vec<f64>,
     pub sync_performance_score: f64,
     pub sync_improvement_rate: f64,
     pub sync_consistency_score: f64,
+    /// Clicks the click bus had played at the last analytics update.
+    pub analytics_clicks: u64,
+    pub calibration: Option<Calibration>,
+    pub calibration_result: Option<TimingStats>,
     // Plugin system
     pub plugins_enabled: bool,
     pub active_plugins: Vec<String>,
@@ -824,31 +732,35 @@ impl Default for Bot {
         let conf = Config::load().unwrap_or_default().fixup();
         let startup_buffer_size = conf.buffer_size;
         let now = Instant::now();
+        let mixer = Mixer::new();
         Self {
+            bus: ClickBus::new(&mixer, &conf.core.dsp),
+            timing: TimingEngine::default(),
+            ticks: TickClock::default(),
             conf: conf.clone(),
             prev_conf: conf,
             last_conf_save: now,
-            mixer: Mixer::new(),
+            mixer,
             #[cfg(not(feature = "geode"))]
             playlayer: PlayLayer::NULL,
-            prev_times: ClickTimes::default(),
+            engine: ClickEngine::default(),
             is_loading_clickpack: Arc::new(AtomicBool::new(false)),
-            prev_pitch: f64::NAN,
-            prev_volume: f64::NAN,
-            prev_spam_offset: f64::NAN,
             buffer_size_changed: false,
             noise_sound: None,
             clickpacks: vec![],
             last_clickpack_reload: now,
+            clickpack_watcher: ClickpackWatcher::spawn(Duration::from_secs(1)),
             // system: std::ptr::null_mut(),
             // channel: std::ptr::null_mut(),
-            env: Env::load(),
+            env: Env::load(built_info::PKG_VERSION),
             toasts: Arc::new(Mutex::new(Toasts::new())),
             // fmod_noise_sound: std::ptr::null_mut(),
             show_fmod_buffersize_warn: false,
             startup_buffer_size,
             is_in_level: false,
             playlayer_time: 0.0,
+            tps: timing::DEFAULT_TPS,
+            game_fps: None,
             clickpack: Clickpack::default(),
             first_launch_dialog_timeout: 3.0,
             level_start: now,
@@ -870,6 +782,7 @@ impl Default for Bot {
             audio_spectrum_data: Vec::new(),
             waveform_history: Vec::new(),
             spectrum_history: Vec::new(),
+            spectrum_peaks: Vec::new(),
             visualization_fps: 60.0,
             visualization_smoothing: 0.8,
             // Initialize sync analytics
@@ -877,10 +790,13 @@ impl Default for Bot {
             sync_analytics_enabled: false,
             sync_quality_trends: Vec::new(),
             sync_latency_trends: Vec::new(),
-            sync_accuracy_trends: Vec::new(),
+            sync_error_trends: Vec::new(),
             sync_performance_score: 0.0,
             sync_improvement_rate: 0.0,
             sync_consistency_score: 0.0,
+            analytics_clicks: 0,
+            calibration: None,
+            calibration_result: None,
             // Initialize plugin system
             plugins_enabled: false,
             active_plugins: Vec::new(),
@@ -966,6 +882,48 @@ fn drag_value<Num: emath::Numeric>(
     resp.unwrap()
 }
 
+/// Short name of a timing unit for labels.
+const fn unit_name(unit: TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Seconds => "sec",
+        TimeUnit::Frames => "frames",
+    }
+}
+
+fn timings_drag_values(ui: &mut egui::Ui, timings: &mut Timings, unit: TimeUnit, tps: f64) {
+    let timings_copy = timings.clone();
+    drag_value(
+        ui,
+        &mut timings.hard,
+        "Hard timing",
+        timings_copy.regular..=f64::INFINITY,
+        "Anything above this time between clicks plays hardclicks/hardreleases",
+    );
+    drag_value(
+        ui,
+        &mut timings.regular,
+        "Regular timing",
+        timings_copy.soft..=timings_copy.hard,
+        "Anything above this time between clicks plays clicks/releases",
+    );
+    drag_value(
+        ui,
+        &mut timings.soft,
+        "Soft timing",
+        0.0..=timings_copy.regular,
+        "Anything above this time between clicks plays softclicks/softreleases",
+    );
+    let soft = match unit {
+        TimeUnit::Seconds => format!("{:.2?}", Duration::from_secs_f64(timings.soft)),
+        TimeUnit::Frames => format!(
+            "{} frames ({:.2?})",
+            timings.soft,
+            Duration::from_secs_f64(unit.unit_to_secs(timings.soft, tps))
+        ),
+    };
+    ui.label(format!("Any value smaller than {soft} plays microclicks/microreleases"));
+}
+
 const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Ubuntu Chromium/37.0.2062.94 Chrome/37.0.2062.94 Safari/537.36";
 
 fn ureq_agent() -> ureq::Agent {
@@ -1000,7 +958,6 @@ impl Bot {
             return;
         }
         log::debug!("starting enhanced kittyaudio playback thread with quality level {}", self.conf.audio_quality_level);
-        self.mixer = Mixer::new();
         let device = self.get_device();
 
         // Enhanced audio settings based on quality level
@@ -1017,7 +974,7 @@ impl Bot {
             self.conf.buffer_size
         };
 
-        self.mixer.init_ex(
+        self.start_mixer(
             device,
             StreamSettings {
                 buffer_size: Some(enhanced_buffer_size),
@@ -1034,6 +991,13 @@ impl Bot {
                   self.conf.audio_quality_level, enhanced_buffer_size, self.conf.audio_sample_rate);
     }
 
+    /// Replace the mixer with a new one that plays through the click bus.
+    fn start_mixer(&mut self, device: Device, settings: StreamSettings) {
+        self.mixer = Mixer::new();
+        self.bus = ClickBus::new(&self.mixer, &self.conf.core.dsp);
+        self.bus.start(device, settings);
+    }
+
     pub unsafe fn init_fmod(&mut self) -> Result<()> {
         /*
         const SYSTEM_SAMPLERATE: i32 = 48_000;
@@ -1157,6 +1121,7 @@ impl Bot {
 
     fn preload_clickpack(&mut self) {
         log::info!("preloading clickpack, order: {:?}", self.env.clickpack_ord);
+        self.watch_loaded_clickpacks();
         use std::thread::JoinHandle;
 
         let preload_clickpack = |path: PathBuf,
@@ -1231,14 +1196,6 @@ impl Bot {
         }
     }
 
-    fn get_pitch(&self) -> f64 {
-        if self.conf.pitch_enabled {
-            utils::f64_range(self.conf.pitch.from..=self.conf.pitch.to)
-        } else {
-            1.0
-        }
-    }
-
     #[allow(unused_variables)]
     pub fn on_init(&mut self, playlayer: usize) {
         #[cfg(not(feature = "geode"))]
@@ -1246,12 +1203,15 @@ impl Bot {
             self.playlayer.addr = playlayer;
         }
 
-        self.prev_times = ClickTimes::default();
-        self.prev_pitch = 0.0;
-        self.prev_volume = self.conf.volume_settings.global_volume;
-        self.prev_spam_offset = 0.0;
+        self.engine.reset();
+        self.engine.set_seed(self.conf.core.seed);
+        self.engine.prev_pitch = 0.0;
+        self.engine.prev_volume = self.conf.core.volume_settings.global_volume;
+        self.engine.prev_spam_offset = 0.0;
         self.is_in_level = true;
         self.level_start = Instant::now();
+        self.timing.reset();
+        self.ticks.reset();
         self.dead_timer = f32::NAN;
     }
 
@@ -1267,7 +1227,10 @@ impl Bot {
         //        t.typ = ClickType::None;
         //    }
         //}
-        self.prev_times = ClickTimes::default();
+        self.engine.reset();
+        self.engine.set_seed(self.conf.core.seed);
+        self.timing.reset();
+        self.ticks.reset();
         self.dead_timer = f32::NAN;
     }
 
@@ -1277,12 +1240,27 @@ impl Bot {
         self.dead_timer = f32::NAN;
     }
 
+    /// Tick rate and frame rate reported by the game. Invalid values are ignored.
+    pub fn set_tps(&mut self, tps: f64, fps: f64) {
+        if tps.is_finite() && tps > 0.0 && tps != self.tps {
+            log::info!("game runs at {tps} TPS");
+            self.tps = tps;
+            self.ticks.set_tps(tps);
+        }
+        self.game_fps = Some(fps).filter(|fps| fps.is_finite() && *fps > 0.0);
+    }
+
+    /// Click settings with the game's tick rate.
+    fn click_settings(&self) -> ClickSettings {
+        self.conf.core.click_settings(self.tps)
+    }
+
     unsafe fn release_buttons(&mut self) {
         log::info!("releasing buttons on death");
         for (button, t) in [
-            (Button::Jump, self.prev_times.jump),
-            (Button::Left, self.prev_times.left),
-            (Button::Right, self.prev_times.right),
+            (Button::Jump, self.engine.prev_times.jump),
+            (Button::Left, self.engine.prev_times.left),
+            (Button::Right, self.engine.prev_times.right),
         ] {
             for (player, time) in t.iter().enumerate() {
                 if time.typ.is_click() && time.time != 0.0 {
@@ -1293,22 +1271,23 @@ impl Bot {
     }
 
     pub unsafe fn on_death(&mut self) {
-        if !self.conf.release_buttons_on_death {
+        if !self.conf.core.release_buttons_on_death {
             return;
         }
 
-        let mut release_delay = self.conf.death_release_delay;
-        let offset = self.conf.death_release_delay_offset;
+        let unit = self.conf.core.timing_unit;
+        let mut release_delay = unit.unit_to_secs(self.conf.core.death_release_delay, self.tps);
+        let offset = unit.unit_to_secs(self.conf.core.death_release_delay_offset, self.tps);
 
         // release all buttons that are still pressed (or do that in the future)
         if release_delay == 0.0 && offset == 0.0 {
             self.release_buttons();
         } else {
             if offset != 0.0 {
-                if self.conf.death_release_delay_neg {
-                    release_delay += utils::f64_range(-offset..=offset);
+                if self.conf.core.death_release_delay_neg {
+                    release_delay += utils::f64_range(&mut self.engine.rng, -offset..=offset);
                 } else {
-                    release_delay += utils::f64_range(0.0..=offset);
+                    release_delay += utils::f64_range(&mut self.engine.rng, 0.0..=offset);
                 }
             }
             if release_delay <= 0.0 {
@@ -1321,6 +1300,7 @@ impl Bot {
     }
 
     pub unsafe fn on_update(&mut self, dt: f32) {
+        self.ticks.advance(dt as f64);
         if !self.dead_timer.is_nan() {
             self.dead_timer += dt;
             if self.dead_timer >= self.dead_timer_limit {
@@ -1328,6 +1308,20 @@ impl Bot {
                 self.release_buttons();
             }
         }
+
+        let capacity = if self.conf.adaptive_buffer_sizing {
+            self.current_sync_mode().get_buffer_size() as usize
+        } else {
+            timing::DEFAULT_CAPACITY
+        };
+        self.timing.set_capacity(capacity);
+        let target_fps = Some(self.conf.target_fps).filter(|&fps| fps > 0.0);
+        self.timing.set_target_fps(target_fps.or(self.game_fps));
+        self.timing.push_frame(dt as f64);
+        if self.conf.adaptive_timing_correction {
+            self.timing.update_correction(self.current_sync_mode().get_correction_factor());
+        }
+
         // Update audio visualization
         self.update_audio_visualization(dt as f64);
         
@@ -1337,45 +1331,38 @@ impl Bot {
 
     #[allow(unused_mut)]
     pub unsafe fn on_action(&mut self, button: Button, mut player2: bool, push: bool) {
-        if self.clickpack.num_sounds == 0 || !self.is_in_level || !self.conf.enabled {
+        if self.clickpack.num_sounds == 0 || !self.is_in_level || !self.conf.core.enabled {
             return;
         }
         #[cfg(not(feature = "geode"))]
         if player2 && !self.playlayer.is_null()
         /* FIXME(2.206): && !self.playlayer.level_settings().is_2player() */
         {
-            player2 = self.conf.force_player2_sounds;
+            player2 = self.conf.core.force_player2_sounds;
         }
         #[cfg(not(feature = "geode"))]
         if !self.playlayer.is_null() {
             return;
         }
-        if button.is_platformer()
-            && !self.conf.force_playing_platformer
-            && !self.clickpack.has_platformer_sounds
-        {
-            return;
-        }
 
         let now = self.time();
-        if now == 0.0 {
-            return;
-        }
-        let prev_time =
-            self.prev_times
-                .get_prev_time(button, player2, self.conf.decouple_platformer);
-        if prev_time.typ.is_click() && push {
+        // other clocks are at 0 before the level starts, but tick time is 0 on the level's
+        // first tick (`is_in_level` is checked above)
+        if now == 0.0 && !self.conf.tick_time {
             return;
         }
-        let dt = (now - prev_time.time).abs();
-        let click_type = ClickType::from_time(push, dt, &self.conf.timings);
-        if self.conf.ignored_click_types.is_ignored(click_type) {
+        let settings = self.click_settings();
+        let Some(mut click) =
+            self.engine
+                .on_action(&mut self.clickpack, &settings, button, player2, push, now)
+        else {
             return;
-        }
+        };
+        let (click_type, dt) = (click.typ, click.dt);
         let use_fmod = self.conf.use_fmod;
 
         // Enhanced audio sound selection with dynamic filtering
-        let mut click = if self.conf.audio_sound_selection && self.conf.dynamic_sound_selection {
+        if self.conf.audio_sound_selection && self.conf.dynamic_sound_selection {
             // Create advanced filter criteria
             let filter_criteria = SoundFilterCriteria::new()
                 .with_duration_range(0.01, 0.2) // 10ms to 200ms duration
@@ -1386,83 +1373,53 @@ impl Bot {
                     4 | 5 => SoundQuality::Ultra,
                     _ => SoundQuality::High,
                 });
-            
-            // Try to get filtered sound first
+
+            // skip the sound the engine just picked too
+            let recent = self.engine.recent_clicks(button, player2, click_type).to_vec();
             if let Some(filtered_sound) = self.clickpack.get_filtered_click(
-                click_type, 
-                player2, 
-                button, 
-                &filter_criteria
+                &mut self.engine.rng,
+                click_type,
+                player2,
+                button,
+                &filter_criteria,
+                &recent,
             ) {
-                filtered_sound
-            } else {
-                // Fallback to enhanced selection
-                let timing_factor = if dt < self.conf.timings.soft {
-                    0.8 // Prefer softer sounds for micro-timing
-                } else if dt > self.conf.timings.hard {
-                    1.2 // Prefer harder sounds for long holds
-                } else {
-                    1.0 // Normal timing
-                };
-                
-                // Apply sound profile filtering
-                let profile_filter = match self.conf.sound_profile {
-                    1 => 0.9, // Bass preference
-                    2 => 1.0, // Balanced
-                    3 => 1.1, // Treble preference
-                    4 => 1.0, // Custom (no change)
-                    _ => 1.0,
-                };
-                
-                // Get sound with enhanced selection criteria
-                let mut selected_sound = self
-                    .clickpack
-                    .get_random_click(click_type, player2, button)
-                    .sound
-                    .clone();
-                
-                // Apply audio enhancement mode if enabled
-                if self.conf.audio_enhancement_mode {
-                    // Enhance audio quality based on settings
-                    let quality_factor = match self.conf.audio_quality_level {
-                        1 => 0.8, // Low quality
-                        2 => 0.9, // Medium quality
-                        3 => 1.0, // High quality
-                        4 => 1.1, // Very high quality
-                        5 => 1.2, // Ultra quality
-                        _ => 1.0,
-                    };
-                    
-                    // Apply quality adjustments
-                    selected_sound.set_playback_rate(PlaybackRate::Factor(quality_factor));
-                }
-                
-                SoundWrapper { sound: selected_sound }
+                self.engine.remember_click(
+                    &settings,
+                    button,
+                    player2,
+                    click_type,
+                    &filtered_sound,
+                    true,
+                );
+                click.start = settings.start_frame(&filtered_sound);
+                click.sound = filtered_sound;
             }
-        } else {
-            // Standard sound selection
-            SoundWrapper { sound: self
-                .clickpack
-                .get_random_click(click_type, player2, button)
-                .sound
-                .clone() }
-        };
+        }
+        // skip the silence before the onset without copying the sound
+        click.sound.seek_to_index(click.start);
+
+        // panned while mixing, so the sound isn't copied for every click
+        let (panning, pan_volume) = utils::mixer_panning(click.pan);
+        click.sound.set_panning(panning);
 
         // Enhanced pitch calculation with adaptive timing and sync optimization
-        let base_pitch = self.get_pitch() * self.conf.click_speedhack;
         let final_pitch = if self.conf.enhanced_audio_timing || self.conf.sync_optimized_audio {
             // Apply timing-based pitch adjustments
-            let timing_adjustment = if dt < self.conf.timings.soft {
+            let timings = settings.timings_for(button, player2);
+            let time = settings.in_timing_unit(dt);
+            let timing_adjustment = if time < timings.soft {
                 0.95 // Slightly lower pitch for micro-timing
-            } else if dt > self.conf.timings.hard {
+            } else if time > timings.hard {
                 1.05 // Slightly higher pitch for long holds
             } else {
                 1.0 // Normal pitch
             };
-            
+
             // Apply sync-aware pitch correction
             let sync_correction = if self.conf.audio_sync_correction {
-                let sync_drift = (dt - (1.0 / 60.0)).abs(); // Calculate drift from 60fps
+                // drift from the game's frame time
+                let sync_drift = self.timing.target_frame_time().map_or(0.0, |t| (dt - t).abs());
                 if sync_drift > 0.001 { // If drift is significant
                     1.0 + (sync_drift * 0.1) // Small pitch correction
                 } else {
@@ -1471,60 +1428,25 @@ impl Bot {
             } else {
                 1.0
             };
-            
-            base_pitch * timing_adjustment * sync_correction
+
+            click.pitch * timing_adjustment * sync_correction
         } else {
-            base_pitch
+            click.pitch
         };
 
         if !use_fmod {
-            click.set_playback_rate(PlaybackRate::Factor(final_pitch));
+            click.sound.set_playback_rate(PlaybackRate::Factor(final_pitch));
         }
 
-        // Enhanced volume calculation with advanced features
+        // Enhanced volume adjustments on top of the engine volume
         {
-            let vol = &self.conf.volume_settings;
-            let mut volume = 1.0;
-            
-            // Apply volume variation with enhanced randomness
-            if vol.volume_var != 0.0 {
-                let variation = if self.conf.advanced_sound_filtering {
-                    // More sophisticated variation based on timing
-                    let timing_factor = (dt / self.conf.timings.regular).clamp(0.1, 2.0);
-                    utils::f64_range(-vol.volume_var..=vol.volume_var) * timing_factor
-                } else {
-                    utils::f64_range(-vol.volume_var..=vol.volume_var)
-                };
-                volume += variation;
-            }
-
-            // Enhanced spam volume calculation
-            if (push || vol.change_releases_volume) && dt < vol.spam_time && vol.enabled {
-                let offset = (vol.spam_time - dt) * vol.spam_vol_offset_factor;
-                self.prev_spam_offset = offset;
-                volume -= offset.min(vol.max_spam_vol_offset);
-            } else {
-                self.prev_spam_offset = 0.0;
-            }
+            let mut volume = click.volume;
 
-            // Platformer volume adjustment with enhanced logic
-            if button.is_platformer() {
-                volume *= vol.platformer_volume_factor;
-                
-                // Additional platformer-specific adjustments
-                if self.conf.enhanced_audio_timing {
-                    let platformer_timing = if dt < 0.1 {
-                        1.1 // Boost volume for quick platformer movements
-                    } else {
-                        1.0
-                    };
-                    volume *= platformer_timing;
-                }
+            // Additional platformer-specific adjustments
+            if button.is_platformer() && self.conf.enhanced_audio_timing && dt < 0.1 {
+                volume *= 1.1; // Boost volume for quick platformer movements
             }
 
-            // Apply global volume after all adjustments
-            volume *= vol.global_volume;
-            
             // Apply sync-aware volume adjustments
             if self.conf.sync_aware_volume {
                 let sync_quality = self.calculate_sync_quality().1;
@@ -1539,21 +1461,23 @@ impl Bot {
                 };
                 volume *= sync_volume_factor;
             }
-            
+
             // Clamp volume to prevent distortion
             volume = volume.clamp(0.0, 2.0);
 
             if !use_fmod {
-                click.set_volume(volume as f32);
+                click.sound.set_volume(volume as f32 * pan_volume);
             }
-            self.prev_volume = volume;
+            self.engine.prev_volume = volume;
         }
 
+        let cut = self.conf.core.cut_sounds
+            && (!click_type.is_release() || self.conf.core.cut_by_releases);
+        // scheduled clicks are cut by the click bus when they start
+        let schedule = !use_fmod && self.conf.schedule_clicks;
+
         // Enhanced sound cutting with better logic
-        if !use_fmod
-            && self.conf.cut_sounds
-            && (!click_type.is_release() || self.conf.cut_by_releases)
-        {
+        if !use_fmod && cut && !schedule {
             for sound in &self.mixer.renderer.guard().sounds {
                 // Enhanced noise sound detection
                 let sound_len = sound.guard().frames.len();
@@ -1577,29 +1501,39 @@ impl Bot {
         }
 
         // Play the enhanced sound
-        self.mixer.play(click.sound);
-        
+        let mut sounds = vec![(click.sound.sound, 0.0)];
+        for layer in click.layers {
+            let mut sound = layer.sound.sound;
+            sound.seek_to_index(layer.start);
+            sound.set_panning(panning);
+            if !use_fmod {
+                sound.set_playback_rate(PlaybackRate::Factor(final_pitch));
+                sound.set_volume((self.engine.prev_volume * layer.volume) as f32 * pan_volume);
+            }
+            // started by the click bus after the delay
+            sounds.push((sound, layer.delay));
+        }
+        if schedule {
+            self.bus.schedule(now, sounds, cut, self.conf.schedule_lookahead);
+        } else if !use_fmod {
+            self.bus.play(now, sounds);
+        } else {
+            for (sound, _) in sounds {
+                self.mixer.play(sound);
+            }
+        }
+
         // Frame-perfect audio processing
         if self.conf.frame_perfect_audio {
             self.process_frame_perfect_audio(click_type, dt);
         }
-        
+
         // Real-time audio analysis
         if self.conf.real_time_audio_analysis {
             self.analyze_audio_performance(dt);
         }
-        
-        // Update timing tracking with enhanced data
-        self.prev_times.set_time(
-            button,
-            player2,
-            ClickTime {
-                time: now,
-                typ: click_type,
-            },
-            self.conf.decouple_platformer,
-        );
-        self.prev_pitch = final_pitch;
+
+        self.engine.prev_pitch = final_pitch;
     }
 
     // New method for frame-perfect audio processing
@@ -1667,288 +1601,90 @@ impl Bot {
 
     #[inline]
     fn time(&self) -> f64 {
+        // ticks are exact already, corrections would only make them differ between runs
+        if self.conf.tick_time {
+            return self.ticks.time();
+        }
         let base_time = {
             #[cfg(feature = "geode")]
-            if self.playlayer_time != 0.0 && self.conf.use_ingame_time {
+            if self.playlayer_time != 0.0 && self.conf.core.use_ingame_time {
                 self.playlayer_time
             } else {
                 self.level_start.elapsed().as_secs_f64()
             }
             #[cfg(not(feature = "geode"))]
-            if !self.playlayer.is_null() && self.conf.use_ingame_time {
+            if !self.playlayer.is_null() && self.conf.core.use_ingame_time {
                 self.playlayer.time()
             } else {
                 self.level_start.elapsed().as_secs_f64()
             }
         };
 
-        // Enhanced sync mode with adaptive timing correction
-        if self.conf.instant_sync_mode || self.conf.adaptive_timing_correction || self.conf.sync_mode != SyncMode::Auto {
-            static mut LAST_TIME: f64 = 0.0;
-            static mut TIME_CORRECTION: f64 = 0.0;
-            static mut FRAME_TIME_BUFFER: Vec<f64> = Vec::new();
-            static mut SYNC_DRIFT_ACCUMULATOR: f64 = 0.0;
-            static mut CURRENT_SYNC_MODE: SyncMode = SyncMode::Auto;
-
-            unsafe {
-                // Auto mode detection
-                if self.conf.auto_mode_detection && self.conf.sync_mode == SyncMode::Auto {
-                    if LAST_TIME > 0.0 {
-                        let frame_time = base_time - LAST_TIME;
-                        FRAME_TIME_BUFFER.push(frame_time);
-                        
-                        // Keep buffer size manageable
-                        let buffer_size = if FRAME_TIME_BUFFER.len() > 120 { 120 } else { FRAME_TIME_BUFFER.len() };
-                        if FRAME_TIME_BUFFER.len() > buffer_size {
-                            FRAME_TIME_BUFFER.remove(0);
-                        }
-
-                        // Detect mode based on timing patterns
-                        if FRAME_TIME_BUFFER.len() > 30 {
-                            let variance = FRAME_TIME_BUFFER.iter()
-                                .map(|&x| (x - FRAME_TIME_BUFFER.iter().sum::<f64>() / FRAME_TIME_BUFFER.len() as f64).powi(2))
-                                .sum::<f64>() / FRAME_TIME_BUFFER.len() as f64;
-                            
-                            CURRENT_SYNC_MODE = if variance < 0.0001 {
-                                SyncMode::Recording
-                            } else if variance > 0.001 {
-                                SyncMode::LivePlay
-                            } else {
-                                SyncMode::Mixed
-                            };
-                        }
-                    }
-                } else {
-                    CURRENT_SYNC_MODE = self.conf.sync_mode;
-                }
-
-                if LAST_TIME > 0.0 {
-                    let frame_time = base_time - LAST_TIME;
-                    FRAME_TIME_BUFFER.push(frame_time);
-                    
-                    // Use adaptive buffer sizing if enabled
-                    let target_buffer_size = if self.conf.adaptive_buffer_sizing {
-                        CURRENT_SYNC_MODE.get_buffer_size()
-                    } else {
-                        120 // Default buffer size
-                    };
-                    
-                    if FRAME_TIME_BUFFER.len() > target_buffer_size as usize {
-                        FRAME_TIME_BUFFER.remove(0);
-                    }
-
-                    if self.conf.adaptive_timing_correction {
-                        let target_frame_time = 1.0 / 60.0;
-                        let avg_frame_time = FRAME_TIME_BUFFER.iter().sum::<f64>() / FRAME_TIME_BUFFER.len() as f64;
-                        
-                        // Calculate drift and apply correction
-                        let current_drift = avg_frame_time - target_frame_time;
-                        SYNC_DRIFT_ACCUMULATOR += current_drift;
-                        
-                        // Use mode-specific correction factor
-                        let correction_factor = CURRENT_SYNC_MODE.get_correction_factor();
-                        TIME_CORRECTION = SYNC_DRIFT_ACCUMULATOR * correction_factor;
-                        
-                        // Clamp correction to prevent extreme values
-                        TIME_CORRECTION = TIME_CORRECTION.clamp(-0.02, 0.02);
-                    }
-                }
-
-                LAST_TIME = base_time;
-                base_time + TIME_CORRECTION
-            }
+        if self.conf.adaptive_timing_correction {
+            base_time + self.timing.correction()
         } else {
             base_time
         }
     }
 
-    fn calculate_sync_quality(&self) -> (String, f64) {
-        // Enhanced real-time sync quality calculation for recording and live play
-        let current_time = self.time();
-        let target_frame_time = 1.0 / 60.0; // Base 60fps target
-
-        // Advanced frame timing analysis with multiple metrics
-        static mut LAST_FRAME_TIME: f64 = 0.0;
-        static mut FRAME_TIME_HISTORY: Vec<f64> = Vec::new();
-        static mut SYNC_DRIFT_HISTORY: Vec<f64> = Vec::new();
-        static mut INSTANT_SYNC_SCORE: f64 = 100.0;
-        static mut RECORDING_MODE_ACTIVE: bool = false;
-        static mut LIVE_PLAY_MODE_ACTIVE: bool = false;
-        static mut CURRENT_SYNC_MODE: SyncMode = SyncMode::Auto;
-
-        unsafe {
-            let frame_time = current_time - LAST_FRAME_TIME;
-            if LAST_FRAME_TIME > 0.0 {
-                FRAME_TIME_HISTORY.push(frame_time);
-                if FRAME_TIME_HISTORY.len() > 120 { // Increased history for better analysis
-                    FRAME_TIME_HISTORY.remove(0);
-                }
-
-                // Calculate instant sync drift with enhanced precision
-                let instant_drift = (frame_time - target_frame_time).abs();
-                SYNC_DRIFT_HISTORY.push(instant_drift);
-                if SYNC_DRIFT_HISTORY.len() > 60 { // Increased for better trend analysis
-                    SYNC_DRIFT_HISTORY.remove(0);
-                }
-
-                // Detect recording vs live play modes based on timing patterns
-                let recent_variance = if FRAME_TIME_HISTORY.len() > 30 {
-                    let recent_frames = &FRAME_TIME_HISTORY[FRAME_TIME_HISTORY.len().saturating_sub(30)..];
-                    let avg = recent_frames.iter().sum::<f64>() / recent_frames.len() as f64;
-                    recent_frames.iter().map(|&x| (x - avg).powi(2)).sum::<f64>() / recent_frames.len() as f64
-                } else {
-                    0.0
-                };
-
-                // Mode detection logic
-                if recent_variance < 0.0001 { // Very stable timing
-                    RECORDING_MODE_ACTIVE = true;
-                    LIVE_PLAY_MODE_ACTIVE = false;
-                } else if recent_variance > 0.001 { // Variable timing
-                    LIVE_PLAY_MODE_ACTIVE = true;
-                    RECORDING_MODE_ACTIVE = false;
-                }
+    /// The sync mode in use. In auto mode with detection on, it follows the pacing of
+    /// the game's frames.
+    fn current_sync_mode(&self) -> SyncMode {
+        if !self.conf.auto_mode_detection || self.conf.sync_mode != SyncMode::Auto {
+            return self.conf.sync_mode;
+        }
+        match self.timing.pacing() {
+            FramePacing::Unknown => SyncMode::Auto,
+            FramePacing::Stable => SyncMode::Recording,
+            FramePacing::Variable => SyncMode::LivePlay,
+            FramePacing::Mixed => SyncMode::Mixed,
+        }
+    }
 
-                // Update current sync mode based on auto detection
-                if self.conf.auto_mode_detection && self.conf.sync_mode == SyncMode::Auto {
-                    CURRENT_SYNC_MODE = if RECORDING_MODE_ACTIVE {
-                        SyncMode::Recording
-                    } else if LIVE_PLAY_MODE_ACTIVE {
-                        SyncMode::LivePlay
-                    } else {
-                        SyncMode::Mixed
-                    };
-                } else {
-                    CURRENT_SYNC_MODE = self.conf.sync_mode;
-                }
-            }
-            LAST_FRAME_TIME = current_time;
+    /// Stats of the last clicks played on the click bus.
+    fn sync_stats(&self) -> TimingStats {
+        TimingStats::new(&self.bus.timings(), SYNC_TOLERANCE)
+    }
 
-            let avg_frame_time = if !FRAME_TIME_HISTORY.is_empty() {
-                FRAME_TIME_HISTORY.iter().sum::<f64>() / FRAME_TIME_HISTORY.len() as f64
-            } else {
-                target_frame_time
-            };
+    /// Quality text and score, the percentage of recent clicks whose spacing matched
+    /// their inputs within [`SYNC_TOLERANCE`].
+    fn calculate_sync_quality(&self) -> (String, f64) {
+        let stats = self.sync_stats();
+        if stats.clicks == 0 {
+            return ("No clicks measured".to_string(), 100.0);
+        }
+        let score = stats.in_tolerance * 100.0;
+        let label = if score >= 95.0 {
+            "🟢 Excellent"
+        } else if score >= 85.0 {
+            "🟢 Good"
+        } else if score >= 70.0 {
+            "🟡 Fair"
+        } else if score >= 50.0 {
+            "🟠 Poor"
+        } else {
+            "🔴 Critical"
+        };
+        let mode = if self.conf.sync_mode_indicators {
+            format!(" [{:?}]", self.current_sync_mode())
+        } else {
+            String::new()
+        };
+        let text = format!(
+            "{label}{mode} ({:.2} ms error, {:.1} ms latency)",
+            stats.error_mean * 1000.0,
+            stats.latency_mean * 1000.0,
+        );
+        (text, score)
+    }
 
-            // Enhanced sync quality calculation with multiple factors
-            let sync_drift = (avg_frame_time - target_frame_time).abs();
-            let base_quality = (1.0 - (sync_drift / target_frame_time).min(1.0)) * 100.0;
-
-            // Calculate stability factor (lower variance = better stability)
-            let stability_factor = if FRAME_TIME_HISTORY.len() > 20 {
-                let variance = FRAME_TIME_HISTORY.iter()
-                    .map(|&x| (x - avg_frame_time).powi(2))
-                    .sum::<f64>() / FRAME_TIME_HISTORY.len() as f64;
-                let stability = 1.0 - (variance.sqrt() / target_frame_time).min(1.0);
-                stability * 100.0
-            } else {
-                100.0
-            };
-
-            // Instant responsiveness factor with mode-specific adjustments
-            let instant_factor = if !SYNC_DRIFT_HISTORY.is_empty() {
-                let recent_drift = SYNC_DRIFT_HISTORY.iter().rev().take(10).sum::<f64>() / 10.0;
-                let base_instant = (1.0 - (recent_drift / target_frame_time).min(1.0)) * 100.0;
-                
-                // Apply mode-specific bonuses
-                let mode_bonus = match CURRENT_SYNC_MODE {
-                    SyncMode::Recording => 1.1, // 10% bonus for recording mode
-                    SyncMode::LivePlay => 1.05, // 5% bonus for live play mode
-                    SyncMode::UltraSync => 1.15, // 15% bonus for ultra sync mode
-                    SyncMode::Performance => 1.02, // 2% bonus for performance mode
-                    _ => 1.0,
-                };
-                base_instant * mode_bonus
-            } else {
-                100.0
-            };
-
-            // Recording-specific quality metrics
-            let recording_bonus = if RECORDING_MODE_ACTIVE {
-                let consistency_score = if FRAME_TIME_HISTORY.len() > 60 {
-                    let recent_60 = &FRAME_TIME_HISTORY[FRAME_TIME_HISTORY.len().saturating_sub(60)..];
-                    let consistency = 1.0 - (recent_60.iter().map(|&x| (x - target_frame_time).abs()).sum::<f64>() / 60.0) / target_frame_time;
-                    consistency.clamp(0.0, 1.0) * 20.0 // Up to 20 bonus points
-                } else {
-                    0.0
-                };
-                consistency_score
-            } else {
-                0.0
-            };
-
-            // Live play specific metrics
-            let live_play_bonus = if LIVE_PLAY_MODE_ACTIVE {
-                let responsiveness_score = if SYNC_DRIFT_HISTORY.len() > 30 {
-                    let recent_drifts = &SYNC_DRIFT_HISTORY[SYNC_DRIFT_HISTORY.len().saturating_sub(30)..];
-                    let avg_recovery_time = recent_drifts.iter().take(5).sum::<f64>() / 5.0;
-                    let responsiveness = 1.0 - (avg_recovery_time / target_frame_time).min(1.0);
-                    responsiveness * 15.0 // Up to 15 bonus points
-                } else {
-                    0.0
-                };
-                responsiveness_score
-            } else {
-                0.0
-            };
-
-            // Combined sync quality score with weighted factors and mode bonuses
-            let combined_score = (base_quality * 0.4) + (stability_factor * 0.25) + (instant_factor * 0.25) + recording_bonus + live_play_bonus;
-            INSTANT_SYNC_SCORE = combined_score.clamp(0.0, 100.0);
-
-            // Dynamic quality text based on comprehensive analysis and mode
-            let mode_text = if self.conf.sync_mode_indicators {
-                match CURRENT_SYNC_MODE {
-                    SyncMode::Auto => "Auto",
-                    SyncMode::LivePlay => "Live",
-                    SyncMode::Recording => "Rec",
-                    SyncMode::Mixed => "Mix",
-                    SyncMode::UltraSync => "Ultra",
-                    SyncMode::Performance => "Perf",
-                }
-            } else {
-                ""
-            };
-
-            let quality_text = if combined_score >= 95.0 {
-                if !mode_text.is_empty() {
-                    format!("🟢 Excellent ({})", mode_text)
-                } else if RECORDING_MODE_ACTIVE {
-                    "🟢 Excellent (Recording)".to_string()
-                } else if LIVE_PLAY_MODE_ACTIVE {
-                    "🟢 Excellent (Live)".to_string()
-                } else {
-                    "🟢 Excellent".to_string()
-                }
-            } else if combined_score >= 85.0 {
-                if !mode_text.is_empty() {
-                    format!("🟢 Good ({})", mode_text)
-                } else if RECORDING_MODE_ACTIVE {
-                    "🟢 Good (Recording)".to_string()
-                } else if LIVE_PLAY_MODE_ACTIVE {
-                    "🟢 Good (Live)".to_string()
-                } else {
-                    "🟢 Good".to_string()
-                }
-            } else if combined_score >= 70.0 {
-                "🟡 Fair".to_string()
-            } else if combined_score >= 50.0 {
-                "🟠 Poor".to_string()
-            } else {
-                "🔴 Critical".to_string()
-            };
-
-            (quality_text.to_string(), combined_score)
-        }
-    }
-
-    fn open_clickbot_toggle_toast(&self) {
-        self.toasts.lock().add(Toast::info(if self.conf.enabled {
-            "Enabled clickbot"
-        } else {
-            "Disabled clickbot"
-        }));
-    }
+    fn open_clickbot_toggle_toast(&self) {
+        self.toasts.lock().add(Toast::info(if self.conf.core.enabled {
+            "Enabled clickbot"
+        } else {
+            "Disabled clickbot"
+        }));
+    }
 
     fn reload_clickpacks(&mut self) -> Result<()> {
         let path = Path::new(".dcd/clickpacks");
@@ -1958,7 +1694,8 @@ impl Bot {
         for entry in dir {
             let entry = entry?;
             let path = entry.path();
-            if path.is_dir() {
+            // zipped clickpacks are loaded in place, same as folders
+            if path.is_dir() || source::is_archive(&path) {
                 let name = path.file_name().unwrap().to_string_lossy().to_string();
                 self.clickpack_db.mark_downloaded(&name, path, true);
                 self.clickpacks.push(name);
@@ -1973,6 +1710,63 @@ impl Bot {
         Ok(())
     }
 
+    /// Paths of the clickpacks listed in `env.json`, i.e. the ones that are loaded.
+    fn loaded_clickpack_paths(&self) -> Vec<PathBuf> {
+        self.env
+            .clickpack_ord
+            .iter()
+            .filter_map(|(clickpack_env, _)| match clickpack_env {
+                ClickpackEnv::Name(name) => Some(Path::new(".dcd/clickpacks").join(name)),
+                ClickpackEnv::Path(path) => Some(path.clone()),
+                ClickpackEnv::None => None,
+            })
+            .collect()
+    }
+
+    /// Watch the loaded clickpacks for hot reload, or nothing if it's turned off. Called
+    /// when either changes, not every frame.
+    fn watch_loaded_clickpacks(&self) {
+        let paths = if self.conf.hot_reload_clickpacks {
+            self.loaded_clickpack_paths()
+        } else {
+            vec![]
+        };
+        self.clickpack_watcher.watch(paths);
+    }
+
+    /// Remember the clickpack loaded for `load_for` in `env.json` and watch it.
+    fn set_clickpack_env(&mut self, clickpack_env: ClickpackEnv, load_for: LoadClickpackFor) {
+        self.env.update(clickpack_env, load_for);
+        self.watch_loaded_clickpacks();
+    }
+
+    fn reload_changed_clickpacks(&mut self) {
+        if !self.conf.hot_reload_clickpacks || self.is_loading_clickpack() {
+            return;
+        }
+        let Some(changes) = self.clickpack_watcher.take_changes() else {
+            return;
+        };
+        log::info!("clickpack files changed ({changes}), reloading: {changes:?}");
+        self.toasts
+            .lock()
+            .add(Toast::info(format!("Reloading clickpack: {changes}")));
+        self.preload_clickpack();
+    }
+
+    /// Sounds are sorted into categories while loading, so the clickpack has to be loaded
+    /// again when the tiers get different folders.
+    fn reload_clickpack_if_tiers_changed(&mut self) {
+        if self.is_loading_clickpack() || self.clickpack.num_sounds == 0 {
+            return;
+        }
+        if self.clickpack.tiers.same_folders(&self.conf.core.tiers()) {
+            return;
+        }
+        log::info!("click tiers changed, reloading clickpack");
+        self.preload_clickpack();
+    }
+
     pub fn draw_ui(&mut self, ctx: &egui::Context) {
         // process hotkeys
         let wants_keyboard = ctx.wants_keyboard_input();
@@ -1992,10 +1786,10 @@ impl Bot {
             self.conf.hidden = !self.conf.hidden;
         }
         if toggle_bot {
-            self.conf.enabled = !self.conf.enabled;
+            self.conf.core.enabled = !self.conf.core.enabled;
         }
         if toggle_noise {
-            self.conf.play_noise = !self.conf.play_noise;
+            self.conf.core.play_noise = !self.conf.core.play_noise;
             self.play_noise();
         }
 
@@ -2069,6 +1863,10 @@ impl Bot {
             self.open_noise_toggle_toast();
         }
 
+        // pick up edited samples even when the menu is closed
+        self.reload_changed_clickpacks();
+        self.reload_clickpack_if_tiers_changed();
+
         // don't draw and don't reload clickpacks if not open
         if self.conf.hidden {
             if matches!(self.conf.toast_visibility, ToastVisibility::AlwaysVisible) {
@@ -2118,7 +1916,7 @@ impl Bot {
                     Stage::Clickpack => self.show_clickpack_window(ui, modal.clone()),
                     Stage::Audio => {
                         if ui
-                            .checkbox(&mut self.conf.enabled, "Enable clickbot")
+                            .checkbox(&mut self.conf.core.enabled, "Enable clickbot")
                             .changed()
                         {
                             self.open_clickbot_toggle_toast();
@@ -2126,7 +1924,7 @@ impl Bot {
                         }
 
                         // ui.separator();
-                        ui.add_enabled_ui(self.conf.enabled, |ui| {
+                        ui.add_enabled_ui(self.conf.core.enabled, |ui| {
                             self.show_audio_window(ui);
         self.draw_audio_visualization(ui);
         self.draw_sync_analytics(ui);
@@ -2258,6 +2056,18 @@ impl Bot {
                 If this is disabled, the config will still be saved on graceful exit",
                 |ui| ui.checkbox(&mut self.conf.autosave_config, "Auto-save config"),
             );
+            help_text(
+                ui,
+                "Reload the selected clickpack when its files are added, removed or changed",
+                |ui| {
+                    if ui
+                        .checkbox(&mut self.conf.hot_reload_clickpacks, "Hot reload clickpacks")
+                        .changed()
+                    {
+                        self.watch_loaded_clickpacks();
+                    }
+                },
+            );
 
             egui::ComboBox::from_label("Toast Visibility")
                 .selected_text(self.conf.toast_visibility.text())
@@ -2377,8 +2187,7 @@ impl Bot {
                 .on_hover_text("Reset to the default audio device")
                 .clicked()
             {
-                self.mixer = Mixer::new();
-                self.mixer.init();
+                self.start_mixer(Device::Default, StreamSettings::default());
                 if let Ok(name) = Device::Default.name() {
                     self.env.selected_device = name.clone();
                     self.toasts
@@ -2407,11 +2216,11 @@ impl Bot {
         // };
         let mut start_kittyaudio_noise = |noise_sound: &mut Option<SoundHandle>| {
             if let Some(mut noise) = self.clickpack.noise.clone() {
-                noise.set_volume(self.conf.noise_volume as f32);
+                noise.set_volume(self.conf.core.noise_volume as f32);
                 noise.set_loop_enabled(true);
                 let frames = noise.frames.len().saturating_sub(1);
                 noise.set_loop_index(0..=frames);
-                noise.set_playback_rate(PlaybackRate::Factor(self.conf.noise_speedhack));
+                noise.set_playback_rate(PlaybackRate::Factor(self.conf.core.noise_speedhack));
                 *noise_sound = Some(self.mixer.play(noise.sound));
             }
         };
@@ -2442,10 +2251,10 @@ impl Bot {
                 );
 
                 // update channel
-                FMOD_Channel_SetVolume(*fmodn, self.conf.noise_volume);
+                FMOD_Channel_SetVolume(*fmodn, self.conf.core.noise_volume);
                 // FMOD_Channel_SetLoopPoints(*fmodn, 0, FMOD_TIMEUNIT_PCM, 1024, FMOD_TIMEUNIT_PCM);
                 FMOD_Channel_SetLoopCount(*fmodn, i32::MAX);
-                FMOD_Channel_SetPitch(*fmodn, self.conf.noise_speedhack as f32);
+                FMOD_Channel_SetPitch(*fmodn, self.conf.core.noise_speedhack as f32);
                 FMOD_System_Update(self.system);
             }
         };
@@ -2454,7 +2263,7 @@ impl Bot {
         stop_kittyaudio_noise(&mut self.noise_sound);
         // stop_fmod_noise(&mut self.fmod_noise_sound);
 
-        if self.conf.play_noise && (self.conf.enabled || self.conf.play_noise_when_disabled) {
+        if self.conf.core.play_noise && (self.conf.core.enabled || self.conf.core.play_noise_when_disabled) {
             if self.conf.use_fmod {
                 // start_fmod_noise(&mut self.fmod_noise_sound);
             } else {
@@ -2464,7 +2273,7 @@ impl Bot {
     }
 
     fn open_noise_toggle_toast(&self) {
-        self.toasts.lock().add(Toast::info(if self.conf.play_noise {
+        self.toasts.lock().add(Toast::info(if self.conf.core.play_noise {
             "Playing noise"
         } else {
             "Stopped playing noise"
@@ -2482,7 +2291,7 @@ impl Bot {
             |ui| {
                 ui.horizontal(|ui| {
                     if ui
-                        .checkbox(&mut self.conf.play_noise, "Play noise")
+                        .checkbox(&mut self.conf.core.play_noise, "Play noise")
                         .on_disabled_hover_text("Your clickpack doesn't have a noise file")
                         .on_hover_text("Play the noise file")
                         .changed()
@@ -2493,7 +2302,7 @@ impl Bot {
 
                     if drag_value(
                         ui,
-                        &mut self.conf.noise_volume,
+                        &mut self.conf.core.noise_volume,
                         "Noise volume",
                         0.0..=f64::INFINITY,
                         "",
@@ -2528,15 +2337,20 @@ impl Bot {
         ui.separator();
 
         ui.collapsing("Timings", |ui| {
-            let timings_copy = self.conf.timings.clone();
-            let timings = &mut self.conf.timings;
-
             help_text(
                 ui,
                 "Use in-game level time instead of real time.\n\
                 Less realistic with practice mode or speedhack",
                 |ui| {
-                    ui.checkbox(&mut self.conf.use_ingame_time, "Use in-game time");
+                    ui.checkbox(&mut self.conf.core.use_ingame_time, "Use in-game time");
+                },
+            );
+            help_text(
+                ui,
+                "Count the level time in physics ticks instead of measuring it.\n\
+                Replaying the same macro then plays the same click types every time",
+                |ui| {
+                    ui.checkbox(&mut self.conf.tick_time, "Tick-based time");
                 },
             );
 
@@ -2635,6 +2449,23 @@ impl Bot {
                     ui.checkbox(&mut self.conf.adaptive_timing_correction, "Adaptive Timing Correction");
                 },
             );
+            if self.conf.adaptive_timing_correction {
+                drag_value(
+                    ui,
+                    &mut self.conf.target_fps,
+                    "Target FPS",
+                    0.0..=f64::INFINITY,
+                    "Frame rate the game should run at, timing drift is measured against it.\n\
+                    0 uses the frame rate the game actually runs at",
+                );
+                if let Some(frame_time) = self.timing.average_frame_time() {
+                    ui.label(format!(
+                        "Game: {:.0} FPS, correction {:.2} ms",
+                        1.0 / frame_time,
+                        self.timing.correction() * 1000.0
+                    ));
+                }
+            }
 
             help_text(
                 ui,
@@ -2663,7 +2494,7 @@ impl Bot {
                 "Plays platformer left/right sounds even if your clickpack doesn't have them",
                 |ui| {
                     ui.checkbox(
-                        &mut self.conf.force_playing_platformer,
+                        &mut self.conf.core.force_playing_platformer,
                         "Force playing platformer sounds",
                     );
                 },
@@ -2674,7 +2505,7 @@ impl Bot {
                 This will not have any effect if you use alternate hook!",
                 |ui| {
                     ui.checkbox(
-                        &mut self.conf.force_player2_sounds,
+                        &mut self.conf.core.force_player2_sounds,
                         "Force playing player 2 sounds",
                     );
                 },
@@ -2684,7 +2515,7 @@ impl Bot {
                 "Makes both platformer sounds have separate timings. Usually sounds bad",
                 |ui| {
                     ui.checkbox(
-                        &mut self.conf.decouple_platformer,
+                        &mut self.conf.core.decouple_platformer,
                         "Decouple platformer sounds",
                     );
                 },
@@ -2780,57 +2611,107 @@ impl Bot {
             }
             help_text(ui, "Releases all held buttons on death", |ui| {
                 ui.checkbox(
-                    &mut self.conf.release_buttons_on_death,
+                    &mut self.conf.core.release_buttons_on_death,
                     "Release buttons on death",
                 );
             });
-            if self.conf.release_buttons_on_death {
+            if self.conf.core.release_buttons_on_death {
+                let unit = unit_name(self.conf.core.timing_unit);
                 drag_value(
                     ui,
-                    &mut self.conf.death_release_delay,
-                    "Release delay (sec)",
+                    &mut self.conf.core.death_release_delay,
+                    format!("Release delay ({unit})"),
                     0.0..=f64::INFINITY,
-                    "Delay before releasing buttons on death in seconds",
+                    "Delay before releasing buttons on death",
                 );
                 ui.horizontal(|ui| {
                     drag_value(
                         ui,
-                        &mut self.conf.death_release_delay_offset,
-                        "+/- (sec)",
+                        &mut self.conf.core.death_release_delay_offset,
+                        format!("+/- ({unit})"),
                         0.0..=f64::INFINITY,
-                        "Random offset for the death release delay in seconds",
+                        "Random offset for the death release delay",
                     );
-                    ui.checkbox(&mut self.conf.death_release_delay_neg, "Negative?");
+                    ui.checkbox(&mut self.conf.core.death_release_delay_neg, "Negative?");
                 });
             }
 
             ui.separator();
 
-            drag_value(
-                ui,
-                &mut timings.hard,
-                "Hard timing",
-                timings_copy.regular..=f64::INFINITY,
-                "Anything above this time between clicks plays hardclicks/hardreleases",
-            );
-            drag_value(
-                ui,
-                &mut timings.regular,
-                "Regular timing",
-                timings_copy.soft..=timings_copy.hard,
-                "Anything above this time between clicks plays clicks/releases",
-            );
-            drag_value(
+            help_text(
                 ui,
-                &mut timings.soft,
-                "Soft timing",
-                0.0..=timings_copy.regular,
-                "Anything above this time between clicks plays softclicks/softreleases",
+                "Measure timings in seconds or in physics ticks of the game.\n\
+                Ticks give the same clicks at any TPS, e.g. for macros made with\n\
+                a physics bypass. Switching converts the current values",
+                |ui| {
+                    let mut unit = self.conf.core.timing_unit;
+                    egui::ComboBox::from_label("Timings in")
+                        .selected_text(format!("{unit:?}"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut unit, TimeUnit::Seconds, "Seconds");
+                            ui.selectable_value(
+                                &mut unit,
+                                TimeUnit::Frames,
+                                format!("Frames ({} TPS)", self.tps),
+                            );
+                        });
+                    if unit != self.conf.core.timing_unit {
+                        self.conf.core.set_timing_unit(unit, self.tps);
+                    }
+                },
             );
-            ui.label(format!(
-                "Any value smaller than {:.2?} plays microclicks/microreleases",
-                Duration::from_secs_f64(timings.soft),
-            ))
+
+            if let Some(tiers) = &mut self.conf.core.tiers {
+                ui.label("Using custom tiers from the config or clickpack.json");
+                let lowest = tiers.iter().map(|t| t.threshold).fold(f64::INFINITY, f64::min);
+                for tier in tiers.iter_mut().filter(|t| t.threshold != lowest) {
+                    drag_value(
+                        ui,
+                        &mut tier.threshold,
+                        format!("{} timing", tier.name),
+                        0.0..=f64::INFINITY,
+                        &format!(
+                            "Anything above this time between clicks plays {}/{}, \
+                            unless a tier with a higher timing matches",
+                            tier.clicks, tier.releases
+                        ),
+                    );
+                }
+                if ui
+                    .button("Use default tiers")
+                    .on_hover_text("Go back to hardclicks, clicks, softclicks and microclicks")
+                    .clicked()
+                {
+                    self.conf.core.tiers = None;
+                }
+                return;
+            }
+
+            let (unit, tps) = (self.conf.core.timing_unit, self.tps);
+            timings_drag_values(ui, &mut self.conf.core.timings, unit, tps);
+
+            ui.collapsing("Per button", |ui| {
+                ui.label(
+                    "Use separate timings for a button, e.g. longer ones for \
+                    platformer movement, which is usually held longer than jumps",
+                );
+                let overrides = &mut self.conf.core.timing_overrides;
+                for button in [Button::Jump, Button::Left, Button::Right] {
+                    for player2 in [false, true] {
+                        let timings = overrides.get_mut(button, player2);
+                        let mut enabled = timings.is_some();
+                        let label = format!("{button:?} (player {})", player2 as u8 + 1);
+                        if ui.checkbox(&mut enabled, label).changed() {
+                            *timings = enabled.then(|| self.conf.core.timings.clone());
+                        }
+                        if let Some(timings) = timings {
+                            ui.indent((button, player2), |ui| {
+                                timings_drag_values(ui, timings, unit, tps)
+                            });
+                        }
+                    }
+                }
+            });
         });
 
         ui.collapsing("Ignored click types", |ui| {
@@ -2838,17 +2719,17 @@ impl Bot {
                 "Ignored click types will not be played. This can be useful for \
                 disabling microreleases, for example",
             );
-            let i = &mut self.conf.ignored_click_types;
-            ui.checkbox(&mut i.hardclicks, "Hardclicks");
-            ui.checkbox(&mut i.hardreleases, "Hardreleases");
-            ui.checkbox(&mut i.clicks, "Clicks");
-            ui.checkbox(&mut i.releases, "Releases");
-            ui.checkbox(&mut i.softclicks, "Softclicks");
-            ui.checkbox(&mut i.softreleases, "Softreleases");
-            ui.checkbox(&mut i.microclicks, "Microclicks");
-            ui.checkbox(&mut i.microreleases, "Microreleases");
+            let tiers = self.conf.core.tiers();
+            let i = &mut self.conf.core.ignored_click_types;
+            for (_, folder) in tiers.categories() {
+                let mut ignored = i.is_ignored(folder);
+                let label = utils::capitalize_first_letter(folder);
+                if ui.checkbox(&mut ignored, label).changed() {
+                    i.set_ignored(folder, ignored);
+                }
+            }
             if i.any_ignored() && ui.button("Reset").clicked() {
-                *i = IgnoredClickTypes::default();
+                *i = dcd_core::engine::IgnoredClickTypes::default();
             }
         });
 
@@ -2857,9 +2738,9 @@ impl Bot {
                 "Pitch variation can make clicks sound more realistic by \
                     changing their pitch randomly.",
             );
-            ui.checkbox(&mut self.conf.pitch_enabled, "Enable pitch variation");
-            ui.add_enabled_ui(self.conf.pitch_enabled, |ui| {
-                let p = &mut self.conf.pitch;
+            ui.checkbox(&mut self.conf.core.pitch_enabled, "Enable pitch variation");
+            ui.add_enabled_ui(self.conf.core.pitch_enabled, |ui| {
+                let p = &mut self.conf.core.pitch;
                 drag_value(
                     ui,
                     &mut p.from,
@@ -2877,19 +2758,100 @@ impl Bot {
             });
         });
 
+        ui.collapsing("Sound variation", |ui| {
+            help_text(
+                ui,
+                "Don't play any of the last N sounds again for the same click type,\n\
+                if the folder has enough sounds. 0 allows repeats.\n\
+                Sounds can be made more or less likely with a suffix like \"click@2.wav\"",
+                |ui| {
+                    ui.add(DragValue::new(&mut self.conf.core.avoid_last).clamp_range(0..=16));
+                    ui.label("Avoid last N sounds");
+                },
+            );
+            help_text(
+                ui,
+                "Play sounds from layer folders like \"clicks_layer\" on top of every click,\n\
+                e.g. a separate switch transient or plastic resonance",
+                |ui| ui.checkbox(&mut self.conf.core.play_layers, "Play layers"),
+            );
+        });
+
+        ui.collapsing("Stereo panning", |ui| {
+            ui.label(
+                "Move players and platformer buttons apart in the stereo field, \
+                so two hands or two players can be told apart. -1 is left, 1 is right",
+            );
+            let pan = &mut self.conf.core.pan;
+            drag_value(ui, &mut pan.player1, "Player 1", -1.0..=1.0, "Pan of player 1");
+            drag_value(ui, &mut pan.player2, "Player 2", -1.0..=1.0, "Pan of player 2");
+            drag_value(
+                ui,
+                &mut pan.left,
+                "Left button",
+                -1.0..=1.0,
+                "Added to the player's pan for the platformer left button",
+            );
+            drag_value(
+                ui,
+                &mut pan.right,
+                "Right button",
+                -1.0..=1.0,
+                "Added to the player's pan for the platformer right button",
+            );
+            drag_value(
+                ui,
+                &mut pan.jitter,
+                "Random pan",
+                0.0..=1.0,
+                "Largest random offset added to the pan of every click",
+            );
+            if *pan != Default::default() && ui.button("Reset").clicked() {
+                *pan = Default::default();
+            }
+        });
+
+        ui.collapsing("Random seed", |ui| {
+            let mut fixed_seed = self.conf.core.seed.is_some();
+            help_text(
+                ui,
+                "Restart the same random sequence on every attempt,\n\
+                so the same inputs always pick the same sounds, pitch and volume",
+                |ui| ui.checkbox(&mut fixed_seed, "Fixed seed"),
+            );
+            if fixed_seed {
+                let seed = self.conf.core.seed.get_or_insert(0);
+                ui.horizontal(|ui| {
+                    ui.add(DragValue::new(seed));
+                    ui.label("Seed");
+                });
+            } else {
+                self.conf.core.seed = None;
+            }
+        });
+
+        ui.collapsing("Sync", |ui| {
+            help_text(
+                ui,
+                "Many samples start with a few milliseconds of silence,\n\
+                which delays the click. This cuts it so the click is heard right away",
+                |ui| ui.checkbox(&mut self.conf.core.trim_silence, "Trim leading silence"),
+            );
+        });
+
         ui.collapsing("Volume settings", |ui| {
             help_text(
                 ui,
                 "Cut overlapping click sounds,\n\
                 changes the sound significantly in spams",
-                |ui| ui.checkbox(&mut self.conf.cut_sounds, "Cut sounds"),
+                |ui| ui.checkbox(&mut self.conf.core.cut_sounds, "Cut sounds"),
             );
-            if self.conf.cut_sounds {
+            if self.conf.core.cut_sounds {
                 help_text(ui, "Allow clicks to be cut by releases", |ui| {
-                    ui.checkbox(&mut self.conf.cut_by_releases, "Cut by releases")
+                    ui.checkbox(&mut self.conf.core.cut_by_releases, "Cut by releases")
                 });
             }
-            let vol = &mut self.conf.volume_settings;
+            let vol = &mut self.conf.core.volume_settings;
             drag_value(
                 ui,
                 &mut vol.global_volume,
@@ -2912,11 +2874,31 @@ impl Bot {
                 "Multiplier of the platformer sound volume. If this is 0.5, \
                 platformer sounds will be played at half volume",
             );
+            let was_normalizing = vol.normalize;
+            help_text(
+                ui,
+                "Play every folder of the clickpack at the same loudness,\n\
+                for packs where some sounds were recorded much louder than others",
+                |ui| ui.checkbox(&mut vol.normalize, "Normalize loudness"),
+            );
+            if vol.normalize && !was_normalizing {
+                // clickpacks are only measured on load if this was on
+                self.clickpack.measure_loudness();
+            }
+            if vol.normalize {
+                drag_value(
+                    ui,
+                    &mut vol.normalize_target,
+                    "Target loudness (dB)",
+                    -60.0..=0.0,
+                    "Loudness every folder is brought to, before the other volume settings",
+                );
+            }
         });
 
         ui.collapsing("Spam volume changes", |ui| {
             ui.label("This can be used to lower volume in spams");
-            let vol = &mut self.conf.volume_settings;
+            let vol = &mut self.conf.core.volume_settings;
             help_text(ui, "Apply this logic to releases", |ui| {
                 ui.checkbox(&mut vol.change_releases_volume, "Change releases volume")
             });
@@ -2948,19 +2930,19 @@ impl Bot {
             ui.horizontal(|ui| {
                 drag_value(
                     ui,
-                    &mut self.conf.click_speedhack,
+                    &mut self.conf.core.click_speedhack,
                     "Click speed",
                     0.0..=f64::INFINITY,
                     "Speed multiplier for clicks/releases",
                 );
-                if self.conf.click_speedhack != 1.0 && ui.button("Reset").clicked() {
-                    self.conf.click_speedhack = 1.0;
+                if self.conf.core.click_speedhack != 1.0 && ui.button("Reset").clicked() {
+                    self.conf.core.click_speedhack = 1.0;
                 }
             });
             ui.horizontal(|ui| {
                 if drag_value(
                     ui,
-                    &mut self.conf.noise_speedhack,
+                    &mut self.conf.core.noise_speedhack,
                     "Noise speed",
                     0.0..=f64::INFINITY,
                     "Speed multiplier for noise. Only useful if your clickpack has a noise file",
@@ -2969,8 +2951,8 @@ impl Bot {
                 {
                     self.play_noise();
                 }
-                if self.conf.noise_speedhack != 1.0 && ui.button("Reset").clicked() {
-                    self.conf.noise_speedhack = 1.0;
+                if self.conf.core.noise_speedhack != 1.0 && ui.button("Reset").clicked() {
+                    self.conf.core.noise_speedhack = 1.0;
                     self.play_noise();
                 }
             });
@@ -3045,6 +3027,186 @@ impl Bot {
             );
         }
 
+        ui.collapsing("Output processing", |ui| {
+            ui.label("Applied to everything that's played, and to rendered replays");
+            let dsp = &mut self.conf.core.dsp;
+
+            ui.checkbox(&mut dsp.eq.enabled, "Equalizer");
+            ui.add_enabled_ui(dsp.eq.enabled, |ui| {
+                egui::ComboBox::from_label("Sound profile")
+                    .selected_text(format!("{:?}", dsp.eq.profile))
+                    .show_ui(ui, |ui| {
+                        for profile in [
+                            SoundProfile::Bass,
+                            SoundProfile::Balanced,
+                            SoundProfile::Treble,
+                            SoundProfile::Custom,
+                        ] {
+                            let text = format!("{profile:?}");
+                            ui.selectable_value(&mut dsp.eq.profile, profile, text);
+                        }
+                    });
+                if dsp.eq.profile != SoundProfile::Custom {
+                    return;
+                }
+                let mut remove = None;
+                for (i, band) in dsp.eq.bands.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source(("eq band", i))
+                            .selected_text(format!("{:?}", band.kind))
+                            .show_ui(ui, |ui| {
+                                for kind in
+                                    [FilterKind::LowShelf, FilterKind::Peak, FilterKind::HighShelf]
+                                {
+                                    ui.selectable_value(&mut band.kind, kind, format!("{kind:?}"));
+                                }
+                            });
+                        ui.add(
+                            DragValue::new(&mut band.freq)
+                                .clamp_range(20.0..=20000.0)
+                                .speed(10.0)
+                                .suffix(" Hz"),
+                        );
+                        ui.add(
+                            DragValue::new(&mut band.gain_db)
+                                .clamp_range(-24.0..=24.0)
+                                .speed(0.1)
+                                .suffix(" dB"),
+                        );
+                        ui.add(
+                            DragValue::new(&mut band.q)
+                                .clamp_range(0.1..=10.0)
+                                .speed(0.01)
+                                .prefix("Q "),
+                        );
+                        if ui.button("Remove").clicked() {
+                            remove = Some(i);
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    dsp.eq.bands.remove(i);
+                }
+                if ui.button("Add band").clicked() {
+                    dsp.eq.bands.push(EqBand {
+                        kind: FilterKind::Peak,
+                        freq: 1000.0,
+                        gain_db: 0.0,
+                        q: 0.707,
+                    });
+                }
+            });
+
+            help_text(
+                ui,
+                "Makes clicks sound like they were recorded in a room.\n\
+                Put impulse response WAVs in .dcd/ir to use a real one",
+                |ui| ui.checkbox(&mut dsp.reverb.enabled, "Reverb"),
+            );
+            ui.add_enabled_ui(dsp.reverb.enabled, |ui| {
+                let r = &mut dsp.reverb;
+                let selected = r
+                    .ir
+                    .as_ref()
+                    .map_or("Built-in room".to_string(), |ir| ir.display().to_string());
+                egui::ComboBox::from_label("Impulse response")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut r.ir, None, "Built-in room");
+                        for ir in reverb::list_impulse_responses() {
+                            let text = ir.display().to_string();
+                            ui.selectable_value(&mut r.ir, Some(ir), text);
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label("Wet/dry mix");
+                    ui.add(egui::Slider::new(&mut r.mix, 0.0..=1.0));
+                });
+                if r.ir.is_none() {
+                    ui.horizontal(|ui| {
+                        ui.label("Room size");
+                        ui.add(egui::Slider::new(&mut r.room_size, 0.0..=1.0));
+                        ui.label("Damping");
+                        ui.add(egui::Slider::new(&mut r.damping, 0.0..=1.0));
+                    });
+                }
+            });
+
+            help_text(
+                ui,
+                "Evens out the loudness of clicks by turning down the loud parts",
+                |ui| ui.checkbox(&mut dsp.compressor.enabled, "Compressor"),
+            );
+            ui.add_enabled_ui(dsp.compressor.enabled, |ui| {
+                let c = &mut dsp.compressor;
+                ui.horizontal(|ui| {
+                    ui.label("Threshold");
+                    ui.add(
+                        DragValue::new(&mut c.threshold_db)
+                            .clamp_range(-60.0..=0.0)
+                            .speed(0.1)
+                            .suffix(" dB"),
+                    );
+                    ui.label("Ratio");
+                    ui.add(
+                        DragValue::new(&mut c.ratio)
+                            .clamp_range(1.0..=20.0)
+                            .speed(0.05)
+                            .suffix(":1"),
+                    );
+                    ui.label("Makeup");
+                    ui.add(
+                        DragValue::new(&mut c.makeup_db)
+                            .clamp_range(0.0..=24.0)
+                            .speed(0.1)
+                            .suffix(" dB"),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Attack");
+                    ui.add(
+                        DragValue::new(&mut c.attack_ms)
+                            .clamp_range(0.0..=200.0)
+                            .speed(0.1)
+                            .suffix(" ms"),
+                    );
+                    ui.label("Release");
+                    ui.add(
+                        DragValue::new(&mut c.release_ms)
+                            .clamp_range(1.0..=1000.0)
+                            .speed(1.0)
+                            .suffix(" ms"),
+                    );
+                });
+            });
+
+            help_text(
+                ui,
+                "Keeps the output from clipping when many clicks overlap",
+                |ui| ui.checkbox(&mut dsp.limiter.enabled, "Limiter"),
+            );
+            ui.add_enabled_ui(dsp.limiter.enabled, |ui| {
+                let l = &mut dsp.limiter;
+                ui.horizontal(|ui| {
+                    ui.label("Ceiling");
+                    ui.add(
+                        DragValue::new(&mut l.ceiling_db)
+                            .clamp_range(-12.0..=0.0)
+                            .speed(0.05)
+                            .suffix(" dB"),
+                    );
+                    ui.label("Release");
+                    ui.add(
+                        DragValue::new(&mut l.release_ms)
+                            .clamp_range(1.0..=500.0)
+                            .speed(1.0)
+                            .suffix(" ms"),
+                    );
+                });
+            });
+        });
+        self.bus.set_settings(&self.conf.core.dsp);
+
         ui.collapsing("Advanced", |ui| {
             // let last_bufsize = self.mixer.renderer.guard().last_buffer_size;
             // ui.label(format!("Real buffer size: {last_bufsize}"));
@@ -3052,7 +3214,7 @@ impl Bot {
                 ui,
                 "Keep playing noise even if the clickbot is disabled",
                 |ui| {
-                    ui.checkbox(&mut self.conf.play_noise_when_disabled, "Play noise when disabled");
+                    ui.checkbox(&mut self.conf.core.play_noise_when_disabled, "Play noise when disabled");
                 },
             );
 
@@ -3093,6 +3255,26 @@ impl Bot {
                     }
                 });
             }
+            help_text(
+                ui,
+                "Start every click at the exact sample matching when it was pressed,\n\
+                so the spacing between clicks doesn't depend on the buffer size.\n\
+                Delays clicks by one buffer plus the look-ahead",
+                |ui| ui.checkbox(&mut self.conf.schedule_clicks, "Sample-accurate clicks"),
+            );
+            ui.add_enabled_ui(self.conf.schedule_clicks, |ui| {
+                ui.horizontal(|ui| {
+                    let mut ms = self.conf.schedule_lookahead * 1000.0;
+                    ui.label("Look-ahead");
+                    let drag = DragValue::new(&mut ms)
+                        .clamp_range(0.0..=50.0)
+                        .speed(0.1)
+                        .suffix(" ms");
+                    if ui.add(drag).changed() {
+                        self.conf.schedule_lookahead = ms / 1000.0;
+                    }
+                });
+            });
             if self.show_fmod_buffersize_warn && self.conf.buffer_size != self.startup_buffer_size {
                 ui.label(
                     RichText::new("WARN: Restart the game to apply FMOD buffer size changes")
@@ -3113,6 +3295,7 @@ impl Bot {
     fn apply_config(&mut self) {
         self.maybe_init_kittyaudio();
         self.play_noise();
+        self.watch_loaded_clickpacks();
 
         #[cfg(not(feature = "geode"))]
         self.maybe_alloc_console();
@@ -3124,10 +3307,10 @@ impl Bot {
     }
 
     fn stop_noise(&mut self) {
-        let prev_play_noise = self.conf.play_noise;
-        self.conf.play_noise = false;
+        let prev_play_noise = self.conf.core.play_noise;
+        self.conf.core.play_noise = false;
         self.play_noise();
-        self.conf.play_noise = prev_play_noise;
+        self.conf.core.play_noise = prev_play_noise;
     }
 
     fn load_clickpack_thread(
@@ -3161,7 +3344,7 @@ impl Bot {
             .show_ui(ui, |ui| {
                 for dirname in &self.clickpacks {
                     let is_loading_clickpack = self.is_loading_clickpack.clone();
-                    let load_for = self.conf.load_clickpack_for;
+                    let load_for = self.conf.core.load_clickpack_for;
                     let path = PathBuf::from(".dcd").join("clickpacks").join(dirname);
                     if ui
                         .selectable_label(&self.clickpack.name == dirname, dirname)
@@ -3182,7 +3365,7 @@ impl Bot {
                                 is_loading_clickpack,
                                 load_for,
                             );
-                            unsafe { BOT.env().env.update(ClickpackEnv::Name(dirname_moved), load_for) };
+                            unsafe { BOT.env().set_clickpack_env(ClickpackEnv::Name(dirname_moved), load_for) };
                         });
                     }
                 }
@@ -3201,7 +3384,7 @@ impl Bot {
                 .clicked()
             {
                 let is_loading_clickpack = self.is_loading_clickpack.clone();
-                let load_for = self.conf.load_clickpack_for;
+                let load_for = self.conf.core.load_clickpack_for;
                 std::thread::spawn(move || {
                     let Some(dir) = FileDialog::new().pick_folder() else {
                         return;
@@ -3220,7 +3403,7 @@ impl Bot {
                         load_for,
                     );
                     unsafe {
-                        BOT.env().env.update(ClickpackEnv::Path(dir), load_for);
+                        BOT.env().set_clickpack_env(ClickpackEnv::Path(dir), load_for);
                     }
                 });
             }
@@ -3242,7 +3425,7 @@ impl Bot {
                 E.g. if you choose Left1, the clickpack will be used for platformer sounds",
                 |ui| {
                     egui::ComboBox::new("select_clickpack_for_combobox", "")
-                        .selected_text(format!("{:?}", self.conf.load_clickpack_for))
+                        .selected_text(format!("{:?}", self.conf.core.load_clickpack_for))
                         .show_ui(ui, |ui| {
                             for load_for in [
                                 LoadClickpackFor::All,
@@ -3254,7 +3437,7 @@ impl Bot {
                                 LoadClickpackFor::Right2,
                             ] {
                                 ui.selectable_value(
-                                    &mut self.conf.load_clickpack_for,
+                                    &mut self.conf.core.load_clickpack_for,
                                     load_for,
                                     format!("{load_for:?}"),
                                 );
@@ -3265,6 +3448,72 @@ impl Bot {
         });
     }
 
+    fn show_clickpack_manifest(&mut self, ui: &mut egui::Ui, manifest: ClickpackManifest) {
+        let credits = match (&manifest.author, &manifest.version) {
+            (Some(author), Some(version)) => Some(format!("By {author}, version {version}")),
+            (Some(author), None) => Some(format!("By {author}")),
+            (None, Some(version)) => Some(format!("Version {version}")),
+            (None, None) => None,
+        };
+        if let Some(credits) = credits {
+            ui.label(credits);
+        }
+        if let Some(description) = &manifest.description {
+            ui.label(description);
+        }
+        if manifest.timings.is_none() && manifest.pitch.is_none() && manifest.tiers.is_none() {
+            return;
+        }
+        if ui
+            .button("Apply recommended settings")
+            .on_hover_text(
+                "Use the timings, tiers and pitch variation from the clickpack's clickpack.json",
+            )
+            .clicked()
+        {
+            if let Some(timings) = manifest.timings {
+                self.conf.core.timings = timings;
+            }
+            if let Some(tiers) = manifest.tiers {
+                self.conf.core.tiers = Some(tiers);
+            }
+            if let Some(pitch) = manifest.pitch {
+                self.conf.core.pitch = pitch;
+                self.conf.core.pitch_enabled = true;
+            }
+        }
+    }
+
+    fn show_clickpack_report(&self, ui: &mut egui::Ui) {
+        let diagnostics = self.clickpack.report.diagnostics();
+        if diagnostics.is_empty() {
+            return;
+        }
+        let num_problems = self.clickpack.report.num_problems();
+        let title = if num_problems == 0 {
+            "Clickpack report".to_string()
+        } else {
+            format!("Clickpack report ({num_problems} problems)")
+        };
+        ui.collapsing(title, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    for diagnostic in diagnostics {
+                        let color = match diagnostic.severity {
+                            Severity::Info => ui.visuals().text_color(),
+                            Severity::Warning => ui.visuals().warn_fg_color,
+                            Severity::Error => ui.visuals().error_fg_color,
+                        };
+                        let label = ui.label(RichText::new(&diagnostic.message).color(color));
+                        if let Some(hint) = &diagnostic.hint {
+                            label.on_hover_text(hint);
+                        }
+                    }
+                });
+        });
+    }
+
     fn show_clickpack_window(&mut self, ui: &mut egui::Ui, modal: Arc<Mutex<Modal>>) {
         let is_loading_clickpack = self.is_loading_clickpack();
         if is_loading_clickpack {
@@ -3278,7 +3527,7 @@ impl Bot {
             if !self.clickpacks.is_empty() {
                 help_text(
                     ui,
-                    "If there's no folders inside .dcd/clickpacks,\n\
+                    "If there's no folders or .zip files inside .dcd/clickpacks,\n\
                     there will be an option to choose the clickpack manually",
                     |ui| {
                         ui.label("Put clickpacks in .dcd/clickpacks");
@@ -3310,7 +3559,7 @@ impl Bot {
                     ui.style_mut().spacing.item_spacing.x = 4.0;
                     if ui.button("🗙").on_hover_text("Unload clickpack").clicked() {
                         self.unload_clickpack();
-                        self.env.update(ClickpackEnv::None, LoadClickpackFor::All);
+                        self.set_clickpack_env(ClickpackEnv::None, LoadClickpackFor::All);
                     }
                 }
                 if is_combobox {
@@ -3334,6 +3583,10 @@ impl Bot {
                 },
             );
         }
+        if let Some(manifest) = &self.clickpack.manifest {
+            self.show_clickpack_manifest(ui, manifest.clone());
+        }
+        self.show_clickpack_report(ui);
         ui.separator();
         ui.collapsing("ClickpackDB", |ui| {
             ui.label(
@@ -3397,9 +3650,9 @@ impl Bot {
                     .striped(true)
                     .show(ui, |ui| {
                         for times in [
-                            self.prev_times.jump,
-                            self.prev_times.left,
-                            self.prev_times.right,
+                            self.engine.prev_times.jump,
+                            self.engine.prev_times.left,
+                            self.engine.prev_times.right,
                         ] {
                             for t in times {
                                 ui.label(format!("{:.3?} | {:?}", t.time, t.typ));
@@ -3409,17 +3662,17 @@ impl Bot {
                     });
                 ui.label(format!(
                     "Last pitch: {:.4} ({}..={})",
-                    self.prev_pitch, self.conf.pitch.from, self.conf.pitch.to
+                    self.engine.prev_pitch, self.conf.core.pitch.from, self.conf.core.pitch.to
                 ));
                 ui.label(format!(
                     "Last volume: {:.4} (+/- {} * {})",
-                    self.prev_volume,
-                    self.conf.volume_settings.volume_var,
-                    self.conf.volume_settings.global_volume
+                    self.engine.prev_volume,
+                    self.conf.core.volume_settings.volume_var,
+                    self.conf.core.volume_settings.global_volume
                 ));
                 ui.label(format!(
                     "Last spam volume offset: -{:.4}",
-                    self.prev_spam_offset
+                    self.engine.prev_spam_offset
                 ));
 
                 let format_path_keep_root = |path: &Path| path.to_string_lossy().replace('\\', "/");
@@ -3449,7 +3702,7 @@ impl Bot {
                 if let Some(select_path) =
                     std::mem::replace(&mut self.clickpack_db.select_clickpack, None)
                 {
-                    self.conf.load_clickpack_for = LoadClickpackFor::All;
+                    self.conf.core.load_clickpack_for = LoadClickpackFor::All;
                     let is_loading_clickpack = self.is_loading_clickpack.clone();
                     let modal_moved = modal.clone();
                     std::thread::spawn(move || {
@@ -3466,7 +3719,7 @@ impl Bot {
                             LoadClickpackFor::All,
                         );
                         unsafe {
-                            BOT.env().env.update(
+                            BOT.env().set_clickpack_env(
                                 ClickpackEnv::Name(
                                     select_path
                                         .file_name()
@@ -3491,49 +3744,50 @@ impl Bot {
         // Update visualization FPS
         self.visualization_fps = (1.0 / dt) as f32;
 
-        // Generate mock waveform data (replace with real audio data)
-        self.generate_waveform_data();
-        self.generate_spectrum_data();
+        // Read what was actually played from the click bus
+        let (frames, sample_rate) = self.bus.recent_output(VISUALIZATION_FRAMES);
+        let mono: Vec<f32> = frames.iter().map(|f| (f.left + f.right) * 0.5).collect();
+        self.generate_waveform_data(&mono);
+        self.generate_spectrum_data(&mono, sample_rate, dt as f32);
 
         // Update history
         self.update_visualization_history();
     }
 
-    fn generate_waveform_data(&mut self) {
-        // Generate mock waveform data based on current audio activity
-        let sample_count = 128;
-        self.audio_waveform_data.clear();
-        
-        for i in 0..sample_count {
-            let time = i as f32 / sample_count as f32;
-            let amplitude = (time * std::f32::consts::PI * 4.0).sin() * 0.5;
-            
-            // Add some randomness based on current audio activity
-            let noise = (time * 10.0).sin() * 0.1;
-            let final_amplitude = (amplitude + noise) * self.conf.visualization_scale;
-            
-            self.audio_waveform_data.push(final_amplitude);
-        }
+    /// One point per chunk of the output: the sample furthest from zero.
+    fn generate_waveform_data(&mut self, mono: &[f32]) {
+        let chunk = (mono.len() / WAVEFORM_POINTS).max(1);
+        let scale = self.conf.visualization_scale;
+        self.audio_waveform_data = mono
+            .chunks(chunk)
+            .map(|c| {
+                let peak = c
+                    .iter()
+                    .fold(0.0f32, |a, &s| if s.abs() > a.abs() { s } else { a });
+                peak * scale
+            })
+            .collect();
     }
 
-    fn generate_spectrum_data(&mut self) {
-        // Generate mock spectrum data (FFT-like)
-        let bin_count = 64;
-        self.audio_spectrum_data.clear();
-        
-        for i in 0..bin_count {
-            let frequency = i as f32 / bin_count as f32;
-            let amplitude = (frequency * std::f32::consts::PI * 2.0).sin() * 0.7;
-            
-            // Add some variation based on time
-            let time_variation = (std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as f32 * 0.001 + frequency * 10.0)
-                .sin() * 0.2;
-            
-            let final_amplitude = (amplitude + time_variation).max(0.0) * self.conf.visualization_scale;
-            self.audio_spectrum_data.push(final_amplitude);
+    fn generate_spectrum_data(&mut self, mono: &[f32], sample_rate: u32, dt: f32) {
+        let scale = self.conf.visualization_scale;
+        let levels = fft::band_levels(mono, sample_rate, SPECTRUM_BANDS);
+        if self.audio_spectrum_data.len() != SPECTRUM_BANDS {
+            self.audio_spectrum_data = vec![0.0; SPECTRUM_BANDS];
+            self.spectrum_peaks = vec![0.0; SPECTRUM_BANDS];
+        }
+
+        // bars jump up immediately and fall smoothly, peaks fall at a fixed rate
+        let smoothing = self.visualization_smoothing;
+        for ((value, peak), db) in self
+            .audio_spectrum_data
+            .iter_mut()
+            .zip(&mut self.spectrum_peaks)
+            .zip(levels)
+        {
+            let level = ((db - SPECTRUM_FLOOR_DB) / -SPECTRUM_FLOOR_DB).clamp(0.0, 1.0) * scale;
+            *value = level.max(*value * smoothing + level * (1.0 - smoothing));
+            *peak = value.max(*peak - PEAK_FALL_SPEED * dt);
         }
     }
 
@@ -3558,66 +3812,153 @@ impl Bot {
 
         ui.separator();
         ui.label("🎵 Audio Visualization");
+        let opacity = self.conf.visualization_opacity;
 
         // Waveform display
         ui.collapsing("Waveform", |ui| {
-            let available_size = ui.available_size();
-            let waveform_height = 60.0;
-            
-            let (rect, response) = ui.allocate_exact_size(
-                vec2(available_size.x, waveform_height),
-                egui::Sense::hover(),
-            );
-
-            // Draw waveform
+            let (rect, _) =
+                ui.allocate_exact_size(vec2(ui.available_width(), 60.0), egui::Sense::hover());
             let painter = ui.painter_at(rect);
-            let points: Vec<egui::Pos2> = self.audio_waveform_data
-                    .iter()
+            let color = Color32::from_rgb(100, 200, 255).gamma_multiply(opacity);
+            let points = |data: &[f32]| -> Vec<egui::Pos2> {
+                let step = rect.width() / (data.len().max(2) - 1) as f32;
+                data.iter()
                     .enumerate()
                     .map(|(i, &amplitude)| {
-                        let x = rect.left() + (i as f32 / self.audio_waveform_data.len() as f32) * rect.width();
-                        let y = rect.center().y + amplitude * rect.height() * 0.4;
-                        egui::Pos2::new(x, y)
+                        let y = rect.center().y - amplitude.clamp(-1.0, 1.0) * rect.height() * 0.5;
+                        egui::pos2(rect.left() + i as f32 * step, y)
                     })
-                    .collect();
-
-                if points.len() > 1 {
-                    painter.line_segment(
-                        [points[0], points[points.len() - 1]],
-                        egui::Stroke::new(2.0, egui::Color32::from_rgb(100, 200, 255)),
-                    );
+                    .collect()
+            };
+            // envelope over the history, drawn above and below the center line
+            let envelope = |f: fn(&[f32]) -> f32| -> Vec<f32> {
+                (0..self.audio_waveform_data.len())
+                    .map(|i| {
+                        let values: Vec<f32> = self
+                            .waveform_history
+                            .iter()
+                            .filter_map(|data| data.get(i).map(|s| s.abs()))
+                            .collect();
+                        f(&values)
+                    })
+                    .collect()
+            };
+            let envelope = match self.conf.waveform_display_mode {
+                WaveformDisplayMode::RealTime => {
+                    painter.add(egui::Shape::line(
+                        points(&self.audio_waveform_data),
+                        egui::Stroke::new(1.5, color),
+                    ));
+                    return;
                 }
+                WaveformDisplayMode::History => {
+                    let n = self.waveform_history.len();
+                    for (i, data) in self.waveform_history.iter().enumerate() {
+                        let alpha = (i + 1) as f32 / n as f32;
+                        painter.add(egui::Shape::line(
+                            points(data),
+                            egui::Stroke::new(1.0, color.gamma_multiply(alpha)),
+                        ));
+                    }
+                    return;
+                }
+                WaveformDisplayMode::Peak => envelope(|v| v.iter().fold(0.0, |a, &s| a.max(s))),
+                WaveformDisplayMode::Average => {
+                    envelope(|v| v.iter().sum::<f32>() / v.len().max(1) as f32)
+                }
+            };
+            let negative: Vec<f32> = envelope.iter().map(|s| -s).collect();
+            for data in [&envelope, &negative] {
+                painter.add(egui::Shape::line(
+                    points(data),
+                    egui::Stroke::new(1.5, color),
+                ));
+            }
         });
 
         // Spectrum display
         ui.collapsing("Spectrum", |ui| {
-            let available_size = ui.available_size();
-            let spectrum_height = 80.0;
-            
-            let (rect, response) = ui.allocate_exact_size(
-                vec2(available_size.x, spectrum_height),
-                egui::Sense::hover(),
-            );
-
-            // Draw spectrum bars
+            let (rect, _) =
+                ui.allocate_exact_size(vec2(ui.available_width(), 80.0), egui::Sense::hover());
             let painter = ui.painter_at(rect);
-                let bar_width = rect.width() / self.audio_spectrum_data.len() as f32;
-                
-                for (i, &amplitude) in self.audio_spectrum_data.iter().enumerate() {
-                    let bar_height = amplitude * rect.height();
-                    let bar_rect = egui::Rect::from_min_size(
-                        egui::Pos2::new(rect.left() + i as f32 * bar_width, rect.bottom() - bar_height),
-                        vec2(bar_width - 1.0, bar_height),
-                    );
-                    
-                    let color = egui::Color32::from_rgb(
-                        (amplitude * 255.0) as u8,
-                        (100.0 + amplitude * 155.0) as u8,
-                        255,
-                    );
-                    
-                    painter.rect_filled(bar_rect, 0.0, color);
+            let data = &self.audio_spectrum_data;
+            let bands = data.len().max(1);
+            let bar_width = rect.width() / bands as f32;
+            let height = |amplitude: f32| amplitude.min(1.0) * rect.height();
+            let color = |amplitude: f32| {
+                Color32::from_rgb(
+                    (amplitude * 255.0) as u8,
+                    (100.0 + amplitude * 155.0) as u8,
+                    255,
+                )
+                .gamma_multiply(opacity)
+            };
+            let top = |i: usize, amplitude: f32| {
+                egui::pos2(
+                    rect.left() + (i as f32 + 0.5) * bar_width,
+                    rect.bottom() - height(amplitude),
+                )
+            };
+            let center = rect.center();
+            let inner = rect.height().min(rect.width()) * 0.15;
+            let outer = rect.height().min(rect.width()) * 0.5;
+            let radial = |i: usize, amplitude: f32| {
+                let angle = i as f32 / bands as f32 * std::f32::consts::TAU;
+                let r = inner + amplitude.min(1.0) * (outer - inner);
+                center + vec2(angle.cos(), angle.sin()) * r
+            };
+
+            match self.conf.spectrum_display_mode {
+                SpectrumDisplayMode::Bars | SpectrumDisplayMode::Area => {
+                    let gap = if self.conf.spectrum_display_mode == SpectrumDisplayMode::Bars {
+                        1.0
+                    } else {
+                        0.0
+                    };
+                    for (i, &amplitude) in data.iter().enumerate() {
+                        let bar_rect = egui::Rect::from_min_size(
+                            egui::pos2(
+                                rect.left() + i as f32 * bar_width,
+                                rect.bottom() - height(amplitude),
+                            ),
+                            vec2(bar_width - gap, height(amplitude)),
+                        );
+                        painter.rect_filled(bar_rect, 0.0, color(amplitude));
+                    }
+                }
+                SpectrumDisplayMode::Line => {
+                    let points = data.iter().enumerate().map(|(i, &a)| top(i, a)).collect();
+                    painter.add(egui::Shape::line(
+                        points,
+                        egui::Stroke::new(1.5, color(0.5)),
+                    ));
+                }
+                SpectrumDisplayMode::Circular => {
+                    for (i, &amplitude) in data.iter().enumerate() {
+                        painter.line_segment(
+                            [radial(i, 0.0), radial(i, amplitude)],
+                            egui::Stroke::new(2.0, color(amplitude)),
+                        );
+                    }
                 }
+            }
+
+            // peak markers for bands that went over the threshold
+            if self.conf.show_audio_peaks {
+                let stroke = egui::Stroke::new(1.5, Color32::YELLOW.gamma_multiply(opacity));
+                for (i, &peak) in self.spectrum_peaks.iter().enumerate() {
+                    if peak <= self.conf.audio_peak_threshold || peak <= 0.0 {
+                        continue;
+                    }
+                    if self.conf.spectrum_display_mode == SpectrumDisplayMode::Circular {
+                        painter.circle_filled(radial(i, peak), 1.5, stroke.color);
+                    } else {
+                        let p = top(i, peak);
+                        let half = bar_width * 0.5;
+                        painter.line_segment([p - vec2(half, 0.0), p + vec2(half, 0.0)], stroke);
+                    }
+                }
+            }
         });
 
         // Visualization controls
@@ -3626,7 +3967,13 @@ impl Bot {
             ui.add(egui::Slider::new(&mut self.conf.visualization_opacity, 0.1..=1.0).text("Opacity"));
             ui.add(egui::Slider::new(&mut self.conf.visualization_scale, 0.1..=3.0).text("Scale"));
             ui.checkbox(&mut self.conf.show_audio_peaks, "Show Peaks");
-            
+            if self.conf.show_audio_peaks {
+                ui.add(
+                    egui::Slider::new(&mut self.conf.audio_peak_threshold, 0.0..=1.0)
+                        .text("Peak threshold"),
+                );
+            }
+
             egui::ComboBox::from_id_source("waveform_mode")
                 .selected_text(format!("{:?}", self.conf.waveform_display_mode))
                 .show_ui(ui, |ui| {
@@ -3653,27 +4000,26 @@ impl Bot {
             return;
         }
 
+        // only add a data point when clicks were played
+        let clicks = self.bus.clicks();
+        if clicks == self.analytics_clicks {
+            return;
+        }
+        self.analytics_clicks = clicks;
+
         let current_time = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs_f64();
-
-        // Calculate current sync metrics
-        let (sync_quality_str, sync_quality_score) = self.calculate_sync_quality();
-        let sync_latency = self.conf.sync_audio_latency;
-        let sync_accuracy = self.calculate_sync_accuracy();
-        let audio_performance = self.calculate_audio_performance();
-        let frame_timing = dt;
-
-        // Create performance data point
+        let stats = self.sync_stats();
         let performance_data = SyncPerformanceData {
             timestamp: current_time,
-            sync_quality: sync_quality_score,
-            sync_latency,
-            sync_accuracy,
-            mode: self.conf.sync_mode,
-            audio_performance,
-            frame_timing,
+            sync_quality: stats.in_tolerance * 100.0,
+            sync_latency: stats.latency_mean,
+            sync_error: stats.error_mean,
+            jitter: stats.jitter,
+            mode: self.current_sync_mode(),
+            frame_timing: dt,
         };
 
         // Add to history
@@ -3691,38 +4037,6 @@ impl Bot {
         self.calculate_performance_scores();
     }
 
-    fn calculate_sync_accuracy(&self) -> f64 {
-        // Calculate sync accuracy based on timing consistency
-        if self.frame_audio_history.len() < 10 {
-            return 0.0;
-        }
-
-        let recent_frames = &self.frame_audio_history[self.frame_audio_history.len().saturating_sub(10)..];
-        let avg_timing = recent_frames.iter().map(|(_, t)| t).sum::<f64>() / recent_frames.len() as f64;
-        
-        let variance = recent_frames.iter()
-            .map(|(_, t)| (t - avg_timing).powi(2))
-            .sum::<f64>() / recent_frames.len() as f64;
-
-        // Convert variance to accuracy score (0-100)
-        let accuracy = (1.0 / (1.0 + variance * 1000.0)) * 100.0;
-        accuracy.clamp(0.0, 100.0)
-    }
-
-    fn calculate_audio_performance(&self) -> f64 {
-        // Calculate audio performance based on latency and buffer efficiency
-        if self.audio_performance_history.is_empty() {
-            return 0.0;
-        }
-
-        let avg_latency = self.audio_performance_history.iter().sum::<f64>() / self.audio_performance_history.len() as f64;
-        let buffer_efficiency = 1.0 - (self.conf.sync_audio_buffer as f64 / 256.0);
-        
-        // Combine latency and buffer efficiency into performance score
-        let performance = (1.0 / (1.0 + avg_latency * 1000.0)) * 70.0 + buffer_efficiency * 30.0;
-        performance.clamp(0.0, 100.0)
-    }
-
     fn update_sync_trends(&mut self) {
         // Update quality trends
         if self.sync_performance_history.len() >= 10 {
@@ -3748,16 +4062,16 @@ impl Bot {
             self.sync_latency_trends = recent_latency;
         }
 
-        // Update accuracy trends
+        // Update error trends
         if self.sync_performance_history.len() >= 10 {
-            let recent_accuracy: Vec<f64> = self.sync_performance_history
+            let recent_error: Vec<f64> = self.sync_performance_history
                 .iter()
                 .rev()
                 .take(10)
-                .map(|data| data.sync_accuracy)
+                .map(|data| data.sync_error)
                 .collect();
             
-            self.sync_accuracy_trends = recent_accuracy;
+            self.sync_error_trends = recent_error;
         }
     }
 
@@ -3770,20 +4084,19 @@ impl Bot {
         let recent_data = &self.sync_performance_history[self.sync_performance_history.len().saturating_sub(10)..];
         
         let avg_quality = recent_data.iter().map(|d| d.sync_quality).sum::<f64>() / recent_data.len() as f64;
-        let avg_accuracy = recent_data.iter().map(|d| d.sync_accuracy).sum::<f64>() / recent_data.len() as f64;
-        let avg_audio = recent_data.iter().map(|d| d.audio_performance).sum::<f64>() / recent_data.len() as f64;
 
-        self.sync_performance_score = (avg_quality * 0.4 + avg_accuracy * 0.4 + avg_audio * 0.2).clamp(0.0, 100.0);
+        self.sync_performance_score = avg_quality.clamp(0.0, 100.0);
 
         // Calculate improvement rate
         if self.sync_performance_history.len() >= 20 {
-            let old_data = &self.sync_performance_history[self.sync_performance_history.len().saturating_sub(20)..10];
+            let len = self.sync_performance_history.len();
+            let old_data = &self.sync_performance_history[len - 20..len - 10];
             let new_data = &self.sync_performance_history[self.sync_performance_history.len().saturating_sub(10)..];
             
             let old_avg = old_data.iter().map(|d| d.sync_quality).sum::<f64>() / old_data.len() as f64;
             let new_avg = new_data.iter().map(|d| d.sync_quality).sum::<f64>() / new_data.len() as f64;
             
-            self.sync_improvement_rate = ((new_avg - old_avg) / old_avg * 100.0).clamp(-50.0, 50.0);
+            self.sync_improvement_rate = ((new_avg - old_avg) / old_avg.max(1.0) * 100.0).clamp(-50.0, 50.0);
         }
 
         // Calculate consistency score
@@ -3805,6 +4118,8 @@ impl Bot {
         ui.label("📊 Sync Performance Analytics");
 
         // Performance overview
+        let stats = self.sync_stats();
+        let buffer_latency = self.bus.buffer_latency();
         ui.collapsing("Performance Overview", |ui| {
             ui.horizontal(|ui| {
                 ui.vertical(|ui| {
@@ -3829,6 +4144,75 @@ impl Bot {
                     ));
                 });
             });
+
+            ui.separator();
+            help_text(
+                ui,
+                "Measured on the last clicks played.\n\
+                Spacing error: how far the time between two clicks is off from the time\n\
+                between their inputs.\n\
+                Latency: time from the click until the audio callback started playing it.",
+                |ui| ui.label(format!("Last {} clicks", stats.clicks)),
+            );
+            ui.label(format!(
+                "In sync (±{:.0} ms): {:.1}%",
+                SYNC_TOLERANCE * 1000.0,
+                stats.in_tolerance * 100.0
+            ));
+            ui.label(format!(
+                "Spacing error: {:.2} ms mean ({:.2} ticks), {:.2} ms max",
+                stats.error_mean * 1000.0,
+                stats.error_mean * self.tps,
+                stats.error_max * 1000.0
+            ));
+            ui.label(format!(
+                "Latency: {:.1} ms mean, {:.1} ms max, {:.2} ms jitter",
+                stats.latency_mean * 1000.0,
+                stats.latency_max * 1000.0,
+                stats.jitter * 1000.0
+            ));
+            ui.label(format!("Output buffer: {:.1} ms", buffer_latency * 1000.0));
+            let fps = self.timing.average_frame_time().map(|t| 1.0 / t);
+            ui.label(format!(
+                "Game: {} TPS, {:.0} FPS",
+                self.tps,
+                self.game_fps.or(fps).unwrap_or_default()
+            ));
+        });
+
+        // Latency calibration
+        self.update_calibration();
+        ui.collapsing("Latency Calibration", |ui| {
+            help_text(
+                ui,
+                "Plays test clicks and measures how long it takes the audio callback\n\
+                to start them. Sound reaches the speakers about one output buffer later",
+                |ui| {
+                    ui.add_enabled_ui(self.calibration.is_none(), |ui| {
+                        if ui.button("Calibrate").clicked() {
+                            self.start_calibration();
+                        }
+                    });
+                },
+            );
+            if let Some(calibration) = &self.calibration {
+                ui.label(format!(
+                    "Measuring... {}/{CALIBRATION_CLICKS}",
+                    calibration.latencies.len()
+                ));
+                ui.ctx().request_repaint();
+            } else if let Some(result) = &self.calibration_result {
+                ui.label(format!(
+                    "Callback latency: {:.1} ms mean, {:.1} ms max, {:.2} ms jitter",
+                    result.latency_mean * 1000.0,
+                    result.latency_max * 1000.0,
+                    result.jitter * 1000.0
+                ));
+                ui.label(format!(
+                    "Estimated output latency: {:.1} ms",
+                    (result.latency_mean + buffer_latency) * 1000.0
+                ));
+            }
         });
 
         // Quality trends chart
@@ -3876,6 +4260,57 @@ impl Bot {
         });
     }
 
+    fn start_calibration(&mut self) {
+        self.bus.take_probes();
+        self.calibration = Some(Calibration {
+            remaining: CALIBRATION_CLICKS,
+            next: Instant::now(),
+            latencies: vec![],
+        });
+    }
+
+    /// Play the next test click and finish once all of them were measured.
+    fn update_calibration(&mut self) {
+        let Some(calibration) = &mut self.calibration else {
+            return;
+        };
+        calibration.latencies.extend(self.bus.take_probes());
+        let now = Instant::now();
+        if calibration.remaining > 0 && now >= calibration.next {
+            self.bus.probe();
+            calibration.remaining -= 1;
+            calibration.next = now + CALIBRATION_INTERVAL;
+        }
+        let measured =
+            calibration.remaining == 0 && calibration.latencies.len() >= CALIBRATION_CLICKS;
+        if !measured && now.duration_since(calibration.next) < CALIBRATION_TIMEOUT {
+            return;
+        }
+
+        let timings: Vec<ClickTiming> = calibration
+            .latencies
+            .iter()
+            .map(|&latency| ClickTiming {
+                latency,
+                error: None,
+            })
+            .collect();
+        self.calibration = None;
+        if timings.is_empty() {
+            log::error!("latency calibration failed: no test click was played");
+            self.calibration_result = None;
+            return;
+        }
+        let result = TimingStats::new(&timings, SYNC_TOLERANCE);
+        log::info!(
+            "measured callback latency over {} clicks: {:.2} ms mean, {:.2} ms jitter",
+            result.clicks,
+            result.latency_mean * 1000.0,
+            result.jitter * 1000.0
+        );
+        self.calibration_result = Some(result);
+    }
+
     fn export_analytics_data(&self) {
         // Export analytics data to JSON file
         let export_data = serde_json::json!({
@@ -3885,6 +4320,12 @@ impl Bot {
                 .as_secs_f64(),
             "performance_history": serde_json::to_value(&self.sync_performance_history).unwrap_or_default(),
             "overall_score": self.sync_performance_score,
+            "calibration": self.calibration_result.map(|result| serde_json::json!({
+                "clicks": result.clicks,
+                "latency_mean": result.latency_mean,
+                "latency_max": result.latency_max,
+                "jitter": result.jitter,
+            })),
             "improvement_rate": self.sync_improvement_rate,
             "consistency_score": self.sync_consistency_score,
         });
@@ -3898,7 +4339,8 @@ impl Bot {
         self.sync_performance_history.clear();
         self.sync_quality_trends.clear();
         self.sync_latency_trends.clear();
-        self.sync_accuracy_trends.clear();
+        self.sync_error_trends.clear();
+        self.bus.clear_timings();
         self.sync_performance_score = 0.0;
         self.sync_improvement_rate = 0.0;
         self.sync_consistency_score = 0.0;
@@ -4105,14 +4547,27 @@ pub enum SpectrumDisplayMode {
     Circular,
 }
 
+/// A latency calibration in progress. Test clicks are played on the click bus one at a
+/// time and their latencies collected.
+#[derive(Debug)]
+pub struct Calibration {
+    remaining: usize,
+    next: Instant,
+    latencies: Vec<f64>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct SyncPerformanceData {
     pub timestamp: f64,
+    /// Percentage of clicks within [`SYNC_TOLERANCE`] of their input spacing.
     pub sync_quality: f64,
+    /// Mean seconds from playing a click until the audio callback started it.
     pub sync_latency: f64,
-    pub sync_accuracy: f64,
+    /// Mean absolute spacing error in seconds.
+    pub sync_error: f64,
+    /// Standard deviation of the latency in seconds.
+    pub jitter: f64,
     pub mode: SyncMode,
-    pub audio_performance: f64,
     pub frame_timing: f64,
 }
 
@@ -4122,9 +4577,9 @@ impl Default for SyncPerformanceData {
             timestamp: 0.0,
             sync_quality: 0.0,
             sync_latency: 0.0,
-            sync_accuracy: 0.0,
+            sync_error: 0.0,
+            jitter: 0.0,
             mode: SyncMode::Auto,
-            audio_performance: 0.0,
             frame_timing: 0.0,
         }
     }
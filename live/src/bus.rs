@@ -0,0 +1,317 @@
+//! The click bus: everything the mixer plays goes through a [`DspChain`] before it
+//! reaches the output device. The processed output is also kept in a ring buffer for
+//! the waveform and spectrum views.
+//!
+//! Clicks can be scheduled to start at an exact frame instead of the next buffer, see
+//! [`ClickScheduler`]. Every click records when the audio callback actually started it,
+//! see [`ClickTiming`]. Sounds of a click can start later than the click itself (layers
+//! with a delay), they're queued again when the click starts.
+
+use dcd_core::{
+    dsp::{DspChain, DspSettings},
+    schedule::{spacing_error, ClickScheduler, ClickTiming},
+};
+use kittyaudio::{
+    DefaultRenderer, Device, Frame, Mixer, Renderer, RendererHandle, Sound, SoundHandle, Stream,
+    StreamSettings,
+};
+use parking_lot::Mutex;
+use std::{collections::VecDeque, sync::Arc, time::Instant};
+
+/// Frames of output kept for the visualization.
+const TAP_LEN: usize = 8192;
+
+/// Buffer size assumed if the stream doesn't set one.
+const DEFAULT_BUFFER_SIZE: u32 = 1024;
+
+/// Click timings kept for the sync stats.
+const TIMINGS_LEN: usize = 256;
+
+/// Length and volume of the calibration click.
+const PROBE_SECS: f64 = 0.005;
+const PROBE_VOLUME: f32 = 0.3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ClickKind {
+    Click,
+    /// Calibration clicks are timed separately.
+    Probe,
+    /// Delayed sounds of a click that already started, not timed.
+    Delayed,
+}
+
+/// Sounds waiting for their frame, each with a delay in seconds after it.
+struct ScheduledClick {
+    frame: u64,
+    sounds: Vec<(Sound, f64)>,
+    /// Stop the previous clicks when this one starts.
+    cut: bool,
+    /// Input timestamp, for the spacing error.
+    time: f64,
+    queued: Instant,
+    kind: ClickKind,
+}
+
+struct BusState {
+    chain: DspChain,
+    /// Last `TAP_LEN` output frames, `tap_pos` is the oldest.
+    tap: Vec<Frame>,
+    tap_pos: usize,
+    /// Next frame to be rendered.
+    frame: u64,
+    buffer_size: u32,
+    scheduler: ClickScheduler,
+    /// Sorted by frame.
+    pending: Vec<ScheduledClick>,
+    /// Scheduled clicks that are playing, for cutting them.
+    playing: Vec<SoundHandle>,
+    timings: VecDeque<ClickTiming>,
+    /// Clicks started so far.
+    clicks: u64,
+    /// Input timestamp and start frame of the last click.
+    last_click: Option<(f64, u64)>,
+    /// Latencies of calibration clicks that weren't taken yet.
+    probes: Vec<f64>,
+}
+
+#[derive(Clone)]
+pub struct ClickBus {
+    mix: RendererHandle<DefaultRenderer>,
+    state: Arc<Mutex<BusState>>,
+}
+
+impl ClickBus {
+    pub fn new(mixer: &Mixer, settings: &DspSettings) -> Self {
+        Self {
+            mix: mixer.renderer.clone(),
+            state: Arc::new(Mutex::new(BusState {
+                // the sample rate is fixed up on the first frame
+                chain: DspChain::new(settings, 48000),
+                tap: vec![Frame::default(); TAP_LEN],
+                tap_pos: 0,
+                frame: 0,
+                buffer_size: DEFAULT_BUFFER_SIZE,
+                scheduler: ClickScheduler::default(),
+                pending: vec![],
+                playing: vec![],
+                timings: VecDeque::with_capacity(TIMINGS_LEN),
+                clicks: 0,
+                last_click: None,
+                probes: vec![],
+            })),
+        }
+    }
+
+    /// Use `settings` from the next frame on. Does nothing if they're the same.
+    ///
+    /// Parameter changes are applied in place. A chain that has to be built again (e.g.
+    /// for another impulse response) is built without holding the lock, so the audio
+    /// thread keeps playing the old one until the new one is ready.
+    pub fn set_settings(&self, settings: &DspSettings) {
+        let (ir, sample_rate) = {
+            let mut state = self.state.lock();
+            if state.chain.settings() == settings || state.chain.update(settings) {
+                return;
+            }
+            (state.chain.impulse_response(), state.chain.sample_rate())
+        };
+        let chain = DspChain::build(settings, sample_rate, ir);
+        // a changed sample rate is picked up by `next_frame`
+        self.state.lock().chain = chain;
+    }
+
+    /// The last `len` frames that were played, oldest first, and their sample rate.
+    pub fn recent_output(&self, len: usize) -> (Vec<Frame>, u32) {
+        let state = self.state.lock();
+        let len = len.min(TAP_LEN);
+        let start = state.tap_pos + TAP_LEN - len;
+        let frames = (start..start + len)
+            .map(|i| state.tap[i % TAP_LEN])
+            .collect();
+        (frames, state.chain.sample_rate())
+    }
+
+    /// Play `sounds` at the frame matching `time` (seconds, from `Bot::time`), each after
+    /// its delay in seconds. Clicks are delayed by one buffer plus `lookahead` seconds, so
+    /// they can always be placed exactly.
+    pub fn schedule(&self, time: f64, sounds: Vec<(Sound, f64)>, cut: bool, lookahead: f64) {
+        let mut state = self.state.lock();
+        let sample_rate = state.chain.sample_rate();
+        let lookahead =
+            state.buffer_size as u64 + (lookahead.max(0.0) * sample_rate as f64).round() as u64;
+        let now = state.frame;
+        let frame = state.scheduler.frame_for(time, now, sample_rate, lookahead);
+        state.queue(ScheduledClick {
+            frame,
+            sounds,
+            cut,
+            time,
+            queued: Instant::now(),
+            kind: ClickKind::Click,
+        });
+    }
+
+    /// Play `sounds` on the next frame rendered, each after its delay in seconds, for an
+    /// action at `time`. The timing is measured the same way as for scheduled clicks.
+    pub fn play(&self, time: f64, sounds: Vec<(Sound, f64)>) {
+        let mut state = self.state.lock();
+        let frame = state.frame;
+        state.queue(ScheduledClick {
+            frame,
+            sounds,
+            cut: false,
+            time,
+            queued: Instant::now(),
+            kind: ClickKind::Click,
+        });
+    }
+
+    /// Play a short test click on the next frame rendered. Its latency is returned by
+    /// [`ClickBus::take_probes`] once it started.
+    pub fn probe(&self) {
+        let mut state = self.state.lock();
+        let sample_rate = state.chain.sample_rate();
+        let len = (PROBE_SECS * sample_rate as f64) as usize;
+        let frames: Vec<Frame> = (0..len)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let env = 1.0 - i as f32 / len as f32;
+                let s = (2.0 * std::f32::consts::PI * 1000.0 * t).sin() * env * PROBE_VOLUME;
+                Frame { left: s, right: s }
+            })
+            .collect();
+        let frame = state.frame;
+        state.queue(ScheduledClick {
+            frame,
+            sounds: vec![(Sound::from_frames(sample_rate, &frames), 0.0)],
+            cut: false,
+            time: 0.0,
+            queued: Instant::now(),
+            kind: ClickKind::Probe,
+        });
+    }
+
+    /// Latencies in seconds of the calibration clicks that started since the last call.
+    pub fn take_probes(&self) -> Vec<f64> {
+        std::mem::take(&mut self.state.lock().probes)
+    }
+
+    /// Timings of the last clicks, oldest first.
+    pub fn timings(&self) -> Vec<ClickTiming> {
+        self.state.lock().timings.iter().copied().collect()
+    }
+
+    /// Number of clicks started so far.
+    pub fn clicks(&self) -> u64 {
+        self.state.lock().clicks
+    }
+
+    pub fn clear_timings(&self) {
+        let mut state = self.state.lock();
+        state.timings.clear();
+        state.last_click = None;
+    }
+
+    /// Length of one output buffer in seconds. Sound reaches the device at least this
+    /// long after the callback rendered it.
+    pub fn buffer_latency(&self) -> f64 {
+        let state = self.state.lock();
+        state.buffer_size as f64 / state.chain.sample_rate() as f64
+    }
+
+    /// Start playing on `device`. Used instead of `Mixer::init_ex`, which would play the
+    /// mixer without the bus.
+    pub fn start(&self, device: Device, settings: StreamSettings) {
+        self.state.lock().buffer_size = settings.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE);
+        let bus = RendererHandle::new(self.clone());
+        std::thread::spawn(move || {
+            if let Err(e) = Stream::new().start_audio_thread(device, settings, bus) {
+                log::error!("failed to start audio stream: {e}");
+            }
+        });
+    }
+}
+
+impl BusState {
+    fn queue(&mut self, click: ScheduledClick) {
+        let i = self.pending.partition_point(|c| c.frame <= click.frame);
+        self.pending.insert(i, click);
+    }
+
+    /// Record the timing of `click`, which starts on the current frame.
+    fn measure(&mut self, click: &ScheduledClick, sample_rate: u32) {
+        let latency = click.queued.elapsed().as_secs_f64();
+        match click.kind {
+            ClickKind::Click => {}
+            ClickKind::Probe => {
+                self.probes.push(latency);
+                return;
+            }
+            ClickKind::Delayed => return,
+        }
+        let error = self
+            .last_click
+            .and_then(|prev| spacing_error(prev, click.time, self.frame, sample_rate));
+        self.last_click = Some((click.time, self.frame));
+        if self.timings.len() == TIMINGS_LEN {
+            self.timings.pop_front();
+        }
+        self.timings.push_back(ClickTiming { latency, error });
+        self.clicks += 1;
+    }
+}
+
+impl Renderer for ClickBus {
+    fn next_frame(&mut self, sample_rate: u32) -> Frame {
+        let mut state = self.state.lock();
+        if state.chain.sample_rate() != sample_rate {
+            state.chain = state.chain.rebuild(state.chain.settings(), sample_rate);
+        }
+
+        let frame = {
+            let mut mix = self.mix.guard();
+            while state
+                .pending
+                .first()
+                .is_some_and(|c| c.frame <= state.frame)
+            {
+                let click = state.pending.remove(0);
+                state.measure(&click, sample_rate);
+                if click.cut {
+                    for sound in state.playing.drain(..) {
+                        sound.seek_to_end();
+                    }
+                    // delayed sounds of the previous clicks would have started playing
+                    state.pending.retain(|c| c.kind != ClickKind::Delayed);
+                }
+                for (sound, delay) in click.sounds {
+                    let delay = (delay.max(0.0) * sample_rate as f64).round() as u64;
+                    if delay > 0 {
+                        let frame = state.frame + delay;
+                        state.queue(ScheduledClick {
+                            frame,
+                            sounds: vec![(sound, 0.0)],
+                            cut: false,
+                            time: click.time,
+                            queued: click.queued,
+                            kind: ClickKind::Delayed,
+                        });
+                        continue;
+                    }
+                    let handle = SoundHandle::new(sound);
+                    mix.sounds.push(handle.clone());
+                    state.playing.push(handle);
+                }
+                state.playing.retain(|sound| !sound.finished());
+            }
+            mix.next_frame(sample_rate)
+        };
+        state.frame += 1;
+
+        let frame = state.chain.process_frame(frame);
+        let pos = state.tap_pos;
+        state.tap[pos] = frame;
+        state.tap_pos = (pos + 1) % TAP_LEN;
+        frame
+    }
+}
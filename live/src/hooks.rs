@@ -63,10 +63,10 @@ pub mod play_layer {
 
 pub mod player_object {
     use crate::{
-        clickpack::Button,
         game::{GameManager, PlayerObject},
         BOT,
     };
+    use dcd_core::clickpack::Button;
 
     retour::static_detour! {
         pub static PUSH_BUTTON_ORIGINAL: unsafe extern "fastcall" fn(PlayerObject, usize, i32);
@@ -107,7 +107,8 @@ pub mod player_object {
 }
 
 pub mod base_game_layer {
-    use crate::{clickpack::Button, game::GameManager, BOT};
+    use crate::{game::GameManager, BOT};
+    use dcd_core::clickpack::Button;
 
     retour::static_detour! {
         //pub static DESTRUCTOR_ORIGINAL: unsafe extern "fastcall" fn(usize);
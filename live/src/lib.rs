@@ -1,5 +1,5 @@
 mod bot;
-mod clickpack;
+mod bus;
 
 #[cfg(not(feature = "geode"))]
 mod game;
@@ -7,10 +7,8 @@ mod game;
 #[cfg(not(feature = "geode"))]
 mod hooks;
 
-mod utils;
-
 use bot::{Bot, BOT};
-use clickpack::Button;
+use dcd_core::clickpack::Button;
 use once_cell::sync::Lazy;
 use std::{ffi::c_void, sync::Once};
 
@@ -242,6 +240,13 @@ unsafe extern "C" fn zcblive_set_playlayer_time(playlayer_time: f64) {
     BOT.set_playlayer_time(playlayer_time);
 }
 
+/// physics ticks and rendered frames per second of the game. optional implementation,
+/// 240 TPS is assumed otherwise
+#[no_mangle]
+unsafe extern "C" fn zcblive_set_tps(tps: f64, fps: f64) {
+    BOT.set_tps(tps, fps);
+}
+
 /// can pass NULL to `playlayer`
 #[no_mangle]
 unsafe extern "C" fn zcblive_on_init(playlayer: usize) {
@@ -275,3 +280,10 @@ unsafe extern "C" fn zcblive_do_use_alternate_hook() -> bool {
 unsafe extern "C" fn zcblive_on_update(dt: f32) {
     BOT.on_update(dt);
 }
+
+/// called once per physics tick, before the tick is processed. optional implementation,
+/// tick-based time counts ticks from `zcblive_on_update` otherwise
+#[no_mangle]
+unsafe extern "C" fn zcblive_on_tick() {
+    BOT.on_tick();
+}
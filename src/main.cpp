@@ -36,12 +36,14 @@ void zcblive_on_action(uint8_t button, bool player2, bool push);
 void zcblive_on_reset();
 void zcblive_set_is_in_level(bool is_in_level);
 void zcblive_set_playlayer_time(double time);
+void zcblive_set_tps(double tps, double fps);
 void zcblive_on_init(PlayLayer* playlayer);
 void zcblive_on_quit();
 void zcblive_on_death();
 bool zcblive_do_force_player2_sounds();
 bool zcblive_do_use_alternate_hook();
 void zcblive_on_update(float dt);
+void zcblive_on_tick();
 }
 
 class GlHook {
@@ -91,6 +93,20 @@ inline double getTime() {
     return playLayer ? from<double>(playLayer, 968) : 0.0;
 }
 
+// tell the bot the tick rate when it changes, `tickDelta` is the length of one physics
+// step (1/240 without a physics bypass)
+void updateTps(float tickDelta) {
+    static float lastTickDelta = 0.0f;
+    static double lastFps = 0.0;
+    double fps = 1.0 / CCDirector::get()->getAnimationInterval();
+    if (tickDelta <= 0.0f || (tickDelta == lastTickDelta && fps == lastFps)) {
+        return;
+    }
+    lastTickDelta = tickDelta;
+    lastFps = fps;
+    zcblive_set_tps(1.0 / tickDelta, fps);
+}
+
 void handleAction(int button, bool player1, bool push, PlayLayer* playLayer) {
     zcblive_on_action(static_cast<uint8_t>(button),
                       !player1 && playLayer &&
@@ -159,6 +175,13 @@ class $modify(GJBaseGameLayer) {
 		zcblive_set_playlayer_time(getTime());
 	}
 
+	// called once per physics step with the step length
+	void processCommands(float dt) {
+		updateTps(dt);
+		zcblive_on_tick();
+		GJBaseGameLayer::processCommands(dt);
+	}
+
 	bool init() {
 		zcblive_on_init(nullptr); // PlayLayer* could be nullptr on Geode
 		return GJBaseGameLayer::init();